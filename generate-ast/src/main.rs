@@ -15,7 +15,7 @@ fn main() {
     }
 
     let output_dir = args.get(1).unwrap();
-    // gen_expr(output_dir);
+    gen_expr(output_dir);
 
     gen_stmt(output_dir);
 }
@@ -29,11 +29,16 @@ fn gen_expr(output_dir: &str) {
         "Literal : Object value".to_owned(),
         format!("Logical : Rc<{base_name}> left, Token operator, Rc<{base_name}> right"),
         format!("Unary : Token operator, Rc<{base_name}> right"),
-        format!("Call : Rc<{base_name}> callee, Token paren, Vec<Rc<{base_name}>> arguments"),
+        format!("Call : Rc<{base_name}> callee, Token paren, Vec<Rc<{base_name}>> arguments, Vec<Token> names"),
         "Variable : Token name".to_owned(),
+        format!("CompoundAssign : Token name, Token operator, Rc<{base_name}> value"),
+        "Update : Token name, Token operator, bool prefix".to_owned(),
+        format!("Conditional : Rc<{base_name}> condition, Rc<{base_name}> then_branch, Rc<{base_name}> else_branch"),
+        "Lambda : Rc<FunctionStmt> declaration".to_owned(),
+        format!("Get : Rc<{base_name}> object, Token name"),
     ];
     let notation = parse_notation(base_name, notation);
-    let import_mod = vec!["core", "error", "token", "rc"];
+    let import_mod = vec!["core", "error", "stmt", "token", "rc"];
     define_ast(output_dir, base_name, &notation, &import_mod).unwrap();
 }
 
@@ -42,15 +47,20 @@ fn gen_stmt(output_dir: &str) {
     let notation = vec![
         format!("Block : Rc<Vec<Rc<{base_name}>>> statements"),
         format!("Expression : Rc<Expr> expression"),
-        format!("Function : Token name, Rc<Vec<Token>> params, Rc<Vec<Rc<{base_name}>>> body"),
+        format!("Function : Token name, Rc<Vec<Token>> params, Rc<Vec<Option<Rc<Expr>>>> defaults, Option<Token> rest, Rc<Vec<Rc<{base_name}>>> body"),
         format!(
             "If : Expr condition, Rc<{base_name}> then_branch, Option<Rc<{base_name}>> else_branch"
         ),
         format!("Print : Rc<Expr> expression"),
-        format!("Var : Token name, Option<Rc<Expr>> initializer"),
-        format!("Return : Token name, Option<Rc<Expr>> value"),
+        format!("Var : Token name, Option<Rc<Expr>> initializer, bool constant"),
+        format!("Return : Token name, Option<Rc<Expr>> value, bool tail_call"),
         format!("Break : usize u"),
         format!("While : Rc<Expr> condition, Rc<{base_name}> body"),
+        format!("For : Option<Rc<{base_name}>> initializer, Option<Rc<Expr>> condition, Option<Rc<Expr>> increment, Rc<{base_name}> body"),
+        format!("Throw : Token keyword, Rc<Expr> value"),
+        format!("Try : Rc<Vec<Rc<{base_name}>>> body, Option<Token> catch_name, Option<Rc<Vec<Rc<{base_name}>>>> catch_body, Option<Rc<Vec<Rc<{base_name}>>>> finally_body"),
+        format!("Import : Token keyword, String path, Option<Token> alias, Rc<Vec<Token>> names"),
+        format!("Export : Token keyword, Rc<{base_name}> declaration"),
     ];
     let notation = parse_notation(base_name, notation);
     let import_mod = vec!["error", "expr", "token", "rc"];
@@ -182,7 +192,7 @@ fn define_impl_struct(
         writeln!(
             buffer,
             "\t\tvisitor.visit_{}_{}(self)",
-            item.derive_name.to_lowercase(),
+            to_snake_case(&item.derive_name),
             item.base_name.to_lowercase()
         )?;
         writeln!(buffer, "\t}}")?;
@@ -203,7 +213,7 @@ fn define_visitor(
         writeln!(
             buffer,
             "\tfn visit_{}_{}(&self, {1}: &{}{}) -> Result<T, LoxResult>;",
-            item.derive_name.to_lowercase(),
+            to_snake_case(&item.derive_name),
             item.base_name.to_lowercase(),
             item.derive_name,
             item.base_name
@@ -214,6 +224,19 @@ fn define_visitor(
     Ok(())
 }
 
+// CompoundAssign -> compound_assign
+fn to_snake_case(name: &str) -> String {
+    let mut result = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            result.push('_');
+        }
+        result.push(c.to_ascii_lowercase());
+    }
+
+    result
+}
+
 #[derive(Debug)]
 struct Notation {
     base_name: String,             // Expr
@@ -5,7 +5,7 @@ use std::{
 
 use crate::{error::LoxResult, interpreter::Interpreter};
 
-use super::Object;
+use super::{Capability, LoxFunction, Object};
 
 #[derive(Clone)]
 pub struct LoxCallable {
@@ -39,11 +39,68 @@ impl Callable for LoxCallable {
     fn arity(&self) -> usize {
         self.func.arity()
     }
+
+    fn max_arity(&self) -> Option<usize> {
+        self.func.max_arity()
+    }
+
+    fn param_names(&self) -> Vec<String> {
+        self.func.param_names()
+    }
+
+    fn call_named(
+        &self,
+        interpreter: &Interpreter,
+        arguments: Vec<Option<Object>>,
+    ) -> Result<Object, LoxResult> {
+        self.func.call_named(interpreter, arguments)
+    }
+
+    fn as_lox_function(&self) -> Option<&LoxFunction> {
+        self.func.as_lox_function()
+    }
+
+    fn capabilities(&self) -> Vec<Capability> {
+        self.func.capabilities()
+    }
 }
 
 pub trait Callable: ToString {
     fn call(&self, interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult>;
 
-    // 函数中的参数个数
+    // 函数中的参数个数（必填参数）
     fn arity(&self) -> usize;
+
+    // 最多可接受的参数个数，None 表示不限（剩余参数）
+    fn max_arity(&self) -> Option<usize> {
+        Some(self.arity())
+    }
+
+    // 形参名称，用于按名称传参；为空表示只支持按位置传参
+    fn param_names(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    // 按形参位置排列的实参，None 表示该位置未传入
+    fn call_named(
+        &self,
+        interpreter: &Interpreter,
+        arguments: Vec<Option<Object>>,
+    ) -> Result<Object, LoxResult> {
+        let arguments = arguments
+            .into_iter()
+            .map(|x| x.unwrap_or(Object::Nil))
+            .collect();
+        self.call(interpreter, arguments)
+    }
+
+    // 尾调用时直接执行函数体，本地函数返回 None
+    fn as_lox_function(&self) -> Option<&LoxFunction> {
+        None
+    }
+
+    // 调用时需要解释器授予的能力
+    fn capabilities(&self) -> Vec<Capability> {
+        Vec::new()
+    }
 }
@@ -0,0 +1,96 @@
+use std::fmt;
+
+// 本地函数可能需要的宿主能力
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Capability {
+    Clock,
+    FileRead,
+    FileWrite,
+    Env,
+    Exit,
+    Stdin,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 6] = [
+        Capability::Clock,
+        Capability::FileRead,
+        Capability::FileWrite,
+        Capability::Env,
+        Capability::Exit,
+        Capability::Stdin,
+    ];
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Capability::Clock => "clock",
+            Capability::FileRead => "file_read",
+            Capability::FileWrite => "file_write",
+            Capability::Env => "env",
+            Capability::Exit => "exit",
+            Capability::Stdin => "stdin",
+        };
+        write!(f, "{name}")
+    }
+}
+
+// 解释器授予脚本的能力集合
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct Capabilities {
+    bits: u8,
+}
+
+impl Capabilities {
+    // 不授予任何能力，用于运行不受信任的脚本
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    pub fn all() -> Self {
+        Capability::ALL.into_iter().collect()
+    }
+
+    pub fn with(mut self, capability: Capability) -> Self {
+        self.bits |= capability.bit();
+        self
+    }
+
+    pub fn without(mut self, capability: Capability) -> Self {
+        self.bits &= !capability.bit();
+        self
+    }
+
+    pub fn contains(&self, capability: Capability) -> bool {
+        self.bits & capability.bit() != 0
+    }
+}
+
+impl FromIterator<Capability> for Capabilities {
+    fn from_iter<T: IntoIterator<Item = Capability>>(iter: T) -> Self {
+        iter.into_iter()
+            .fold(Self::none(), |caps, capability| caps.with(capability))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Capabilities, Capability};
+
+    #[test]
+    fn test_capabilities() {
+        let caps = Capabilities::none().with(Capability::Clock);
+        assert!(caps.contains(Capability::Clock));
+        assert!(!caps.contains(Capability::FileRead));
+
+        let caps = Capabilities::all().without(Capability::FileWrite);
+        assert!(Capability::ALL
+            .into_iter()
+            .all(|c| caps.contains(c) == (c != Capability::FileWrite)));
+    }
+}
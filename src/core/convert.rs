@@ -0,0 +1,258 @@
+use std::mem;
+
+use super::{Foreign, Object};
+
+// Lox 值转换为 Rust 类型，供本地函数读取参数
+pub trait FromLox: Sized {
+    // 期望的类型名称，用于错误信息
+    fn lox_type() -> String;
+
+    fn from_lox(value: &Object) -> Option<Self>;
+}
+
+// Rust 类型转换为 Lox 值，供本地函数返回结果
+pub trait IntoLox {
+    fn into_lox(self) -> Object;
+
+    // 转换后占用的内存，在转换前计入预算
+    fn size(&self) -> usize {
+        0
+    }
+}
+
+impl FromLox for Object {
+    fn lox_type() -> String {
+        "any value".to_string()
+    }
+
+    fn from_lox(value: &Object) -> Option<Self> {
+        Some(value.clone())
+    }
+}
+
+impl FromLox for f64 {
+    fn lox_type() -> String {
+        "number".to_string()
+    }
+
+    fn from_lox(value: &Object) -> Option<Self> {
+        match value {
+            Object::Number(num) => Some(*num),
+            _ => None,
+        }
+    }
+}
+
+impl FromLox for i64 {
+    fn lox_type() -> String {
+        "integer".to_string()
+    }
+
+    fn from_lox(value: &Object) -> Option<Self> {
+        // i64::MAX as f64 舍入为 2^63，已超出 i64 的范围，上界不能取等
+        match value {
+            Object::Number(num)
+                if num.fract() == 0.0 && *num >= i64::MIN as f64 && *num < i64::MAX as f64 =>
+            {
+                Some(*num as i64)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl FromLox for bool {
+    fn lox_type() -> String {
+        "bool".to_string()
+    }
+
+    fn from_lox(value: &Object) -> Option<Self> {
+        match value {
+            Object::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+// 参数为所有权类型，&str 只能作为返回值
+impl FromLox for String {
+    fn lox_type() -> String {
+        "string".to_string()
+    }
+
+    fn from_lox(value: &Object) -> Option<Self> {
+        match value {
+            Object::Str(s) => Some(s.clone()),
+            _ => None,
+        }
+    }
+}
+
+// nil 转换为 None
+impl<T: FromLox> FromLox for Option<T> {
+    fn lox_type() -> String {
+        format!("{} or nil", T::lox_type())
+    }
+
+    fn from_lox(value: &Object) -> Option<Self> {
+        match value {
+            Object::Nil => Some(None),
+            value => T::from_lox(value).map(Some),
+        }
+    }
+}
+
+impl<T: FromLox> FromLox for Vec<T> {
+    fn lox_type() -> String {
+        format!("list of {}", T::lox_type())
+    }
+
+    fn from_lox(value: &Object) -> Option<Self> {
+        match value {
+            Object::List(items) => items.borrow().iter().map(T::from_lox).collect(),
+            _ => None,
+        }
+    }
+}
+
+impl FromLox for Foreign {
+    fn lox_type() -> String {
+        "foreign".to_string()
+    }
+
+    fn from_lox(value: &Object) -> Option<Self> {
+        match value {
+            Object::Foreign(foreign) => Some(foreign.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl IntoLox for Object {
+    fn into_lox(self) -> Object {
+        self
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            Object::Str(s) => s.len(),
+            Object::List(items) => items.borrow().len() * mem::size_of::<Object>(),
+            _ => 0,
+        }
+    }
+}
+
+impl IntoLox for Foreign {
+    fn into_lox(self) -> Object {
+        Object::Foreign(self)
+    }
+}
+
+impl IntoLox for () {
+    fn into_lox(self) -> Object {
+        Object::Nil
+    }
+}
+
+impl IntoLox for f64 {
+    fn into_lox(self) -> Object {
+        Object::Number(self)
+    }
+}
+
+impl IntoLox for i64 {
+    fn into_lox(self) -> Object {
+        Object::Number(self as f64)
+    }
+}
+
+impl IntoLox for bool {
+    fn into_lox(self) -> Object {
+        Object::Bool(self)
+    }
+}
+
+impl IntoLox for String {
+    fn into_lox(self) -> Object {
+        Object::Str(self)
+    }
+
+    fn size(&self) -> usize {
+        self.len()
+    }
+}
+
+impl IntoLox for &str {
+    fn into_lox(self) -> Object {
+        Object::Str(self.to_string())
+    }
+
+    fn size(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T: IntoLox> IntoLox for Option<T> {
+    fn into_lox(self) -> Object {
+        match self {
+            Some(value) => value.into_lox(),
+            None => Object::Nil,
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.as_ref().map_or(0, IntoLox::size)
+    }
+}
+
+impl<T: IntoLox> IntoLox for Vec<T> {
+    fn into_lox(self) -> Object {
+        Object::new_list(self.into_iter().map(IntoLox::into_lox).collect())
+    }
+
+    fn size(&self) -> usize {
+        self.len() * mem::size_of::<Object>() + self.iter().map(IntoLox::size).sum::<usize>()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FromLox, IntoLox, Object};
+
+    #[test]
+    fn test_from_lox() {
+        assert_eq!(f64::from_lox(&Object::Number(1.5)), Some(1.5));
+        assert_eq!(i64::from_lox(&Object::Number(1.5)), None);
+        assert_eq!(i64::from_lox(&Object::Number(-3.0)), Some(-3));
+        assert_eq!(
+            i64::from_lox(&Object::Number(-(2f64.powi(63)))),
+            Some(i64::MIN)
+        );
+        assert_eq!(i64::from_lox(&Object::Number(2f64.powi(63))), None);
+        assert_eq!(bool::from_lox(&Object::Nil), None);
+        assert_eq!(Option::<bool>::from_lox(&Object::Nil), Some(None));
+        assert_eq!(
+            Vec::<String>::from_lox(&vec!["a", "b"].into_lox()),
+            Some(vec!["a".to_string(), "b".to_string()])
+        );
+        assert_eq!(Vec::<f64>::from_lox(&vec!["a"].into_lox()), None);
+        assert_eq!(<Option<Vec<i64>>>::lox_type(), "list of integer or nil");
+    }
+
+    #[test]
+    fn test_into_lox() {
+        assert_eq!(3i64.into_lox(), Object::Number(3.0));
+        assert_eq!(Some("x").into_lox(), Object::Str("x".to_string()));
+        assert_eq!(None::<f64>.into_lox(), Object::Nil);
+        assert_eq!(().into_lox(), Object::Nil);
+    }
+
+    #[test]
+    fn test_into_lox_size() {
+        let item = std::mem::size_of::<Object>();
+        assert_eq!("abc".size(), 3);
+        assert_eq!(1.5.size(), 0);
+        assert_eq!(Some("ab".to_string()).size(), 2);
+        assert_eq!(vec!["a", "bc"].size(), 2 * item + 3);
+    }
+}
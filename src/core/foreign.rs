@@ -0,0 +1,101 @@
+use std::any::Any;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::error::LoxResult;
+use crate::interpreter::Interpreter;
+
+use super::{Callable, Capability, LoxCallable, Object};
+
+// 宿主传入脚本的 Rust 值，脚本中只能调用宿主注册的方法
+#[derive(Clone)]
+pub struct Foreign {
+    // 只占一个指针，避免 Object 变大
+    inner: Rc<ForeignValue>,
+}
+
+struct ForeignValue {
+    type_name: String,
+    value: Box<dyn Any>,
+}
+
+impl Foreign {
+    pub fn new<T: Any>(type_name: &str, value: T) -> Self {
+        Self {
+            inner: Rc::new(ForeignValue {
+                type_name: type_name.to_string(),
+                value: Box::new(value),
+            }),
+        }
+    }
+
+    pub fn type_name(&self) -> &str {
+        &self.inner.type_name
+    }
+
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.inner.value.downcast_ref::<T>()
+    }
+}
+
+impl fmt::Display for Foreign {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<foreign {}>", self.type_name())
+    }
+}
+
+impl fmt::Debug for Foreign {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl PartialEq for Foreign {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+// 绑定了接收者的方法，调用时接收者作为第一个实参传入
+pub struct BoundMethod {
+    receiver: Object,
+    method: LoxCallable,
+}
+
+impl BoundMethod {
+    pub fn new(receiver: Object, method: LoxCallable) -> Self {
+        Self { receiver, method }
+    }
+}
+
+impl fmt::Display for BoundMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.method)
+    }
+}
+
+impl Callable for BoundMethod {
+    fn arity(&self) -> usize {
+        self.method.arity()
+    }
+
+    fn max_arity(&self) -> Option<usize> {
+        self.method.max_arity()
+    }
+
+    // 被包装方法的第一个形参是接收者，不能按名称传入
+    fn param_names(&self) -> Vec<String> {
+        self.method.param_names().into_iter().skip(1).collect()
+    }
+
+    fn capabilities(&self) -> Vec<Capability> {
+        self.method.capabilities()
+    }
+
+    fn call(&self, interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+        let mut with_receiver = Vec::with_capacity(arguments.len() + 1);
+        with_receiver.push(self.receiver.clone());
+        with_receiver.extend(arguments);
+        self.method.call(interpreter, with_receiver)
+    }
+}
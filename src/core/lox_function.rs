@@ -1,7 +1,8 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, fmt, mem, rc::Rc};
 
 use crate::environment::Environment;
 use crate::error::LoxResult;
+use crate::expr::Expr;
 use crate::interpreter::Interpreter;
 use crate::stmt::*;
 use crate::token::Token;
@@ -10,6 +11,8 @@ use super::{Callable, Object};
 
 pub struct LoxFunction {
     params: Rc<Vec<Token>>,
+    defaults: Rc<Vec<Option<Rc<Expr>>>>,
+    rest: Option<Token>,
     name: Token,
     body: Rc<Vec<Rc<Stmt>>>,
     closure: Rc<RefCell<Environment>>,
@@ -19,36 +22,119 @@ impl LoxFunction {
     pub fn new(declaration: &FunctionStmt, closure: &Rc<RefCell<Environment>>) -> Self {
         Self {
             params: declaration.params.clone(),
+            defaults: declaration.defaults.clone(),
+            rest: declaration.rest.clone(),
             name: declaration.name.clone(),
             body: declaration.body.clone(),
             closure: closure.clone(),
         }
     }
+
+    pub fn name(&self) -> &str {
+        &self.name.lexeme
+    }
+
+    // 绑定参数并执行函数体，函数体中的尾调用以 LoxResult::TailCall 返回
+    fn invoke(
+        &self,
+        interpreter: &Interpreter,
+        arguments: Vec<Option<Object>>,
+    ) -> Result<Object, LoxResult> {
+        let environment = Rc::new(RefCell::new(Environment::new_enclosing(
+            self.closure.clone(),
+        )));
+
+        // 缺省的参数在调用时于函数环境中求值，可引用前面的参数
+        let mut arguments = arguments.into_iter();
+        for (param, default) in self.params.iter().zip(self.defaults.iter()) {
+            let value = match (arguments.next().flatten(), default) {
+                (Some(value), _) => value,
+                (None, Some(expr)) => interpreter.evaluate_in(expr, environment.clone())?,
+                (None, None) => Object::Nil,
+            };
+            environment.borrow_mut().define(param.as_string(), value);
+        }
+        if let Some(ref rest) = self.rest {
+            let rest_args: Vec<Object> = arguments.flatten().collect();
+            interpreter.allocate(rest_args.len() * mem::size_of::<Object>(), rest)?;
+            environment
+                .borrow_mut()
+                .define(rest.as_string(), Object::new_list(rest_args));
+        }
+
+        match interpreter.execute_block(&self.body, environment) {
+            Err(LoxResult::Return { value }) => Ok(value),
+            Ok(_) => Ok(Object::Nil),
+            Err(e) => Err(e),
+        }
+    }
 }
 
-impl ToString for LoxFunction {
-    fn to_string(&self) -> String {
-        format!("<Func {}>", self.name.lexeme)
+impl fmt::Display for LoxFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<fn {}>", self.name.lexeme)
     }
 }
 
 impl Callable for LoxFunction {
     fn arity(&self) -> usize {
-        self.params.len()
+        self.defaults.iter().filter(|d| d.is_none()).count()
+    }
+
+    fn max_arity(&self) -> Option<usize> {
+        match self.rest {
+            Some(_) => None,
+            None => Some(self.params.len()),
+        }
+    }
+
+    fn param_names(&self) -> Vec<String> {
+        self.params.iter().map(|param| param.as_string()).collect()
     }
 
     fn call(&self, interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
-        let mut environment = Environment::new_enclosing(self.closure.clone());
+        self.call_named(interpreter, arguments.into_iter().map(Some).collect())
+    }
 
-        for (param, value) in self.params.iter().zip(arguments.into_iter()) {
-            environment.define(param.as_string(), value)
-        }
+    fn call_named(
+        &self,
+        interpreter: &Interpreter,
+        arguments: Vec<Option<Object>>,
+    ) -> Result<Object, LoxResult> {
+        let mut result = self.invoke(interpreter, arguments);
 
-        match interpreter.execute_block(&self.body, environment) {
-            Err(LoxResult::Return { value }) => Ok(value),
-            Ok(_) => Ok(Object::Nil),
-            Err(e) => Err(e),
+        // 尾调用在此循环中执行，所有尾调用共用一个栈帧，每次替换为被调用的函数
+        let mut pushed = false;
+        while let Err(LoxResult::TailCall {
+            callee,
+            paren,
+            arguments,
+        }) = result
+        {
+            if pushed {
+                interpreter.replace_frame(&callee, &paren);
+            } else if let Err(e) = interpreter.push_frame(&callee, &paren) {
+                return Err(e);
+            } else {
+                pushed = true;
+            }
+
+            // 出栈前附加调用栈，错误才能定位到尾调用的函数
+            result = match callee.as_lox_function() {
+                Some(function) => function.invoke(interpreter, arguments),
+                None => callee.call_named(interpreter, arguments),
+            }
+            .map_err(|e| e.at_call(&paren).with_backtrace(interpreter.backtrace()));
+        }
+        if pushed {
+            interpreter.pop_frame();
         }
+
+        result
+    }
+
+    fn as_lox_function(&self) -> Option<&LoxFunction> {
+        Some(self)
     }
 }
 
@@ -1,8 +1,19 @@
 mod callable;
+mod capability;
+mod convert;
+mod foreign;
 mod lox_function;
+mod module;
 mod object;
 
 pub use callable::Callable;
 pub use callable::LoxCallable;
+pub use capability::Capabilities;
+pub use capability::Capability;
+pub use convert::FromLox;
+pub use convert::IntoLox;
+pub use foreign::BoundMethod;
+pub use foreign::Foreign;
 pub use lox_function::LoxFunction;
+pub use module::Module;
 pub use object::Object;
@@ -0,0 +1,64 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::environment::Environment;
+
+use super::Object;
+
+// 被导入的模块，拥有独立的全局环境，只有导出的成员对外可见
+pub struct Module {
+    path: String,
+    environment: Rc<RefCell<Environment>>,
+    exports: HashSet<String>,
+}
+
+impl Module {
+    pub fn new(
+        path: String,
+        environment: Rc<RefCell<Environment>>,
+        exports: HashSet<String>,
+    ) -> Self {
+        Self {
+            path,
+            environment,
+            exports,
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    // 模块导出的成员
+    pub fn get(&self, name: &str) -> Result<Object, String> {
+        let value = self.environment.borrow().lookup_local(name);
+        match value {
+            Some(value) if self.exports.contains(name) => Ok(value),
+            Some(_) => Err(format!(
+                "`{}` is not exported by module `{}`",
+                name, self.path
+            )),
+            None => Err(format!("module `{}` has no member `{}`", self.path, name)),
+        }
+    }
+}
+
+impl fmt::Display for Module {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<module {}>", self.path)
+    }
+}
+
+impl fmt::Debug for Module {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl PartialEq for Module {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
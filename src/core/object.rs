@@ -1,15 +1,105 @@
-use std::fmt;
+use std::{cell::RefCell, fmt, rc::Rc};
 
-use super::LoxCallable;
+use super::{Foreign, LoxCallable, Module};
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Clone)]
 pub enum Object {
     Number(f64),
     Str(String),
     Nil,
-    True,
-    False,
+    Bool(bool),
     Func(LoxCallable),
+    List(Rc<RefCell<Vec<Object>>>),
+    // 运行时错误被 catch 捕获后转换成的错误对象
+    Error { message: String, line: usize },
+    // 宿主传入的 Rust 值
+    Foreign(Foreign),
+    // import 导入的模块
+    Module(Rc<Module>),
+}
+
+impl Object {
+    pub fn new_list(items: Vec<Object>) -> Self {
+        Self::List(Rc::new(RefCell::new(items)))
+    }
+
+    // 类型名称，用于错误信息
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::Number(_) => "number",
+            Self::Str(_) => "string",
+            Self::Nil => "nil",
+            Self::Bool(_) => "bool",
+            Self::Func(_) => "function",
+            Self::List(_) => "list",
+            Self::Error { .. } => "error",
+            Self::Foreign(_) => "foreign",
+            Self::Module(_) => "module",
+        }
+    }
+}
+
+// 列表可以包含自身，打印及比较时记录正在访问的列表以避免无限递归
+type ListPtr = *const RefCell<Vec<Object>>;
+
+impl Object {
+    // 已在访问中的列表打印为 `[...]`
+    fn fmt_list(&self, f: &mut fmt::Formatter<'_>, visiting: &mut Vec<ListPtr>) -> fmt::Result {
+        let items = match self {
+            Self::List(items) => items,
+            other => return write!(f, "{}", other),
+        };
+        if visiting.contains(&Rc::as_ptr(items)) {
+            return write!(f, "[...]");
+        }
+
+        visiting.push(Rc::as_ptr(items));
+        let result = Self::fmt_items(&items.borrow(), f, visiting);
+        visiting.pop();
+
+        result
+    }
+
+    // 逐个写入元素，写入出错时立即停止
+    fn fmt_items(
+        items: &[Object],
+        f: &mut fmt::Formatter<'_>,
+        visiting: &mut Vec<ListPtr>,
+    ) -> fmt::Result {
+        write!(f, "[")?;
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            item.fmt_list(f, visiting)?;
+        }
+        write!(f, "]")
+    }
+
+    // 再次遇到正在比较的一对列表时视为相等
+    fn eq_list(&self, other: &Self, visiting: &mut Vec<(ListPtr, ListPtr)>) -> bool {
+        let (left, right) = match (self, other) {
+            (Self::List(left), Self::List(right)) => (left, right),
+            _ => return self == other,
+        };
+        let pair = (Rc::as_ptr(left), Rc::as_ptr(right));
+        if Rc::ptr_eq(left, right) || visiting.contains(&pair) {
+            return true;
+        }
+
+        let (left, right) = (left.borrow(), right.borrow());
+        if left.len() != right.len() {
+            return false;
+        }
+        visiting.push(pair);
+        let equal = left
+            .iter()
+            .zip(right.iter())
+            .all(|(x, y)| x.eq_list(y, visiting));
+        visiting.pop();
+
+        equal
+    }
 }
 
 impl fmt::Display for Object {
@@ -18,9 +108,124 @@ impl fmt::Display for Object {
             Self::Number(x) => write!(f, "{}", x),
             Self::Str(x) => write!(f, "{}", x),
             Self::Nil => write!(f, "nil"),
-            Self::True => write!(f, "true"),
-            Self::False => write!(f, "false"),
-            Self::Func(func) => write!(f, "{:?}", func),
+            Self::Bool(x) => write!(f, "{}", x),
+            Self::Func(func) => write!(f, "{}", func),
+            Self::List(_) => self.fmt_list(f, &mut Vec::new()),
+            Self::Error { message, line } => write!(f, "<error: {} (line {})>", message, line),
+            Self::Foreign(foreign) => write!(f, "{}", foreign),
+            Self::Module(module) => write!(f, "{}", module),
+        }
+    }
+}
+
+impl fmt::Debug for Object {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number(x) => f.debug_tuple("Number").field(x).finish(),
+            Self::Str(x) => f.debug_tuple("Str").field(x).finish(),
+            Self::Nil => write!(f, "Nil"),
+            Self::Bool(x) => f.debug_tuple("Bool").field(x).finish(),
+            Self::Func(func) => f.debug_tuple("Func").field(func).finish(),
+            Self::List(_) => write!(f, "List({})", self),
+            Self::Error { message, line } => f
+                .debug_struct("Error")
+                .field("message", message)
+                .field("line", line)
+                .finish(),
+            Self::Foreign(foreign) => f.debug_tuple("Foreign").field(foreign).finish(),
+            Self::Module(module) => f.debug_tuple("Module").field(module).finish(),
         }
     }
 }
+
+impl PartialEq for Object {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Number(a), Self::Number(b)) => a == b,
+            (Self::Str(a), Self::Str(b)) => a == b,
+            (Self::Nil, Self::Nil) => true,
+            (Self::Bool(a), Self::Bool(b)) => a == b,
+            (Self::Func(a), Self::Func(b)) => a == b,
+            (Self::List(_), Self::List(_)) => self.eq_list(other, &mut Vec::new()),
+            (
+                Self::Error { message, line },
+                Self::Error {
+                    message: other_message,
+                    line: other_line,
+                },
+            ) => message == other_message && line == other_line,
+            (Self::Foreign(a), Self::Foreign(b)) => a == b,
+            (Self::Module(a), Self::Module(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl From<bool> for Object {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<f64> for Object {
+    fn from(value: f64) -> Self {
+        Self::Number(value)
+    }
+}
+
+impl From<String> for Object {
+    fn from(value: String) -> Self {
+        Self::Str(value)
+    }
+}
+
+impl From<&str> for Object {
+    fn from(value: &str) -> Self {
+        Self::Str(value.to_string())
+    }
+}
+
+impl From<()> for Object {
+    fn from(_: ()) -> Self {
+        Self::Nil
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Object;
+
+    #[test]
+    fn test_from_primitives() {
+        assert_eq!(Object::from(true), Object::Bool(true));
+        assert_eq!(Object::from(1.5), Object::Number(1.5));
+        assert_eq!(Object::from("lox"), Object::Str("lox".to_string()));
+        assert_eq!(Object::from(()), Object::Nil);
+        assert_eq!(Object::from(false).to_string(), "false");
+    }
+
+    #[test]
+    fn test_self_referential_list() {
+        let list = Object::new_list(vec![Object::Number(1.0)]);
+        if let Object::List(items) = &list {
+            items.borrow_mut().push(list.clone());
+        }
+        assert_eq!(list.to_string(), "[1, [...]]");
+        assert_eq!(format!("{:?}", list), "List([1, [...]])");
+
+        let other = Object::new_list(vec![Object::Number(1.0)]);
+        if let Object::List(items) = &other {
+            items.borrow_mut().push(other.clone());
+        }
+        assert_eq!(list, other);
+        assert_ne!(
+            list,
+            Object::new_list(vec![Object::Number(1.0), Object::Nil])
+        );
+
+        // 同一列表出现多次但没有自引用时正常打印
+        let shared = Object::new_list(vec![Object::Nil]);
+        let outer = Object::new_list(vec![shared.clone(), shared]);
+        assert_eq!(outer.to_string(), "[[nil], [nil]]");
+    }
+}
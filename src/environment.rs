@@ -1,5 +1,5 @@
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 
 use crate::{core::Object, error::LoxResult, token::Token};
@@ -7,6 +7,8 @@ use crate::{core::Object, error::LoxResult, token::Token};
 #[derive(Debug, Default)]
 pub struct Environment {
     values: HashMap<String, Object>,
+    // const 声明的名称，不能被赋值
+    constants: HashSet<String>,
     enclosing: Option<Rc<RefCell<Environment>>>,
 }
 
@@ -14,6 +16,7 @@ impl Environment {
     pub fn new() -> Self {
         Self {
             values: HashMap::new(),
+            constants: HashSet::new(),
             enclosing: None,
         }
     }
@@ -21,14 +24,53 @@ impl Environment {
     pub fn new_enclosing(enclosing: Rc<RefCell<Environment>>) -> Self {
         Self {
             values: HashMap::new(),
+            constants: HashSet::new(),
             enclosing: Some(enclosing),
         }
     }
 
+    // 复制当前环境中的绑定，外层环境保持共享，用于 for 循环每次迭代的新绑定
+    pub fn copy(&self) -> Self {
+        Self {
+            values: self.values.clone(),
+            constants: self.constants.clone(),
+            enclosing: self.enclosing.clone(),
+        }
+    }
+
     pub fn define(&mut self, name: String, value: Object) {
+        self.constants.remove(&name);
         self.values.insert(name, value);
     }
 
+    pub fn define_constant(&mut self, name: String, value: Object) {
+        self.constants.insert(name.clone());
+        self.values.insert(name, value);
+    }
+
+    // 当前环境中的常量，不查找外层环境
+    pub fn is_constant(&self, name: &str) -> bool {
+        self.constants.contains(name)
+    }
+
+    // 外层环境是否为 environment
+    pub fn is_enclosed_by(&self, environment: &Rc<RefCell<Environment>>) -> bool {
+        matches!(&self.enclosing, Some(enclosing) if Rc::ptr_eq(enclosing, environment))
+    }
+
+    // 按名称查找，不存在时返回 None，不报告错误
+    pub fn lookup(&self, name: &str) -> Option<Object> {
+        match self.values.get(name) {
+            Some(obj) => Some(obj.clone()),
+            None => self.enclosing.as_ref()?.borrow().lookup(name),
+        }
+    }
+
+    // 只在当前环境中查找，不查找外层环境
+    pub fn lookup_local(&self, name: &str) -> Option<Object> {
+        self.values.get(name).cloned()
+    }
+
     pub fn get(&self, name: &Token) -> Result<Object, LoxResult> {
         if let Some(obj) = self.values.get(&name.lexeme) {
             Ok(obj.clone())
@@ -43,7 +85,12 @@ impl Environment {
     }
 
     pub fn assign(&mut self, name: &Token, value: Object) -> Result<(), LoxResult> {
-        if self.values.contains_key(&name.lexeme) {
+        if self.constants.contains(&name.lexeme) {
+            Err(LoxResult::runtime_error(
+                name,
+                format!("cannot assign to constant `{}`", name.lexeme),
+            ))
+        } else if self.values.contains_key(&name.lexeme) {
             self.values.insert(name.as_string(), value);
             Ok(())
         } else if let Some(ref enclosing) = self.enclosing {
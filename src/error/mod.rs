@@ -1,13 +1,61 @@
-use crate::{core::Object, token::Token};
+use std::rc::Rc;
+
+use crate::{
+    core::{LoxCallable, Object},
+    interpreter::CallFrame,
+    token::Token,
+};
+
+// 报告错误时最多打印的调用栈帧数
+const MAX_REPORT_FRAMES: usize = 20;
 
 #[derive(Debug)]
 pub enum LoxResult {
     // #[default]
-    ParseError { token: Token, message: String },
-    RuntimeError { token: Token, message: String },
-    LoxError { line: usize, message: String },
-    SystemError { message: String },
-    Return { value: Object },
+    ParseError {
+        token: Token,
+        message: String,
+    },
+    RuntimeError {
+        token: Token,
+        message: String,
+        backtrace: Vec<CallFrame>,
+    },
+    LoxError {
+        line: usize,
+        message: String,
+    },
+    SystemError {
+        message: String,
+    },
+    // 本地函数中的错误，在调用处转换为运行时错误
+    NativeError {
+        message: String,
+    },
+    // 超出执行预算或被宿主取消，不能被 catch 捕获
+    Interrupted {
+        message: String,
+    },
+    // 脚本调用 exit 结束运行，不能被 catch 捕获，由宿主决定是否退出进程
+    Exit {
+        code: i32,
+    },
+    Return {
+        value: Object,
+    },
+    // throw 抛出的值
+    Throw {
+        token: Token,
+        value: Object,
+        // 抛出处所在的模块，未被捕获时在报告中注明；使用 Rc 避免增大错误类型
+        module: Option<Rc<String>>,
+    },
+    // 尾调用，由外层 LoxFunction::call 循环执行
+    TailCall {
+        callee: LoxCallable,
+        paren: Token,
+        arguments: Vec<Option<Object>>,
+    },
     Break,
 }
 
@@ -18,44 +66,135 @@ pub enum LoxResult {
 // }
 
 impl LoxResult {
+    // 错误在构造时不打印，可能被 catch 捕获，由顶层统一报告
     pub fn error(line: usize, message: String) -> Self {
-        let err = Self::LoxError { line, message };
-        err.report("Error");
-        err
+        Self::LoxError { line, message }
     }
 
     pub fn parse_error(token: Token, message: String) -> Self {
-        let err = Self::ParseError { token, message };
-        err.report("Parse Error");
-        err
+        Self::ParseError { token, message }
     }
 
+    // 运行时错误在传播过程中才会附加调用栈
     pub fn runtime_error(token: &Token, message: String) -> Self {
-        let err = Self::RuntimeError {
+        Self::RuntimeError {
             token: token.clone(),
             message,
-        };
-        err.report("Runtime Error");
-        err
+            backtrace: Vec::new(),
+        }
     }
 
     pub fn system_error(message: String) -> Self {
-        let err = Self::SystemError { message };
-        err.report("System Error");
+        Self::SystemError { message }
+    }
+
+    pub fn native_error(message: String) -> Self {
+        Self::NativeError { message }
+    }
+
+    // 本地函数的错误定位到调用处
+    pub fn at_call(self, paren: &Token) -> Self {
+        match self {
+            Self::NativeError { message } => Self::runtime_error(paren, message),
+            err => err,
+        }
+    }
 
-        err
+    // 模块中产生的错误在信息中附加模块路径
+    pub fn in_module(self, path: &str) -> Self {
+        match self {
+            Self::ParseError { token, message } => Self::ParseError {
+                token,
+                message: format!("in module `{}`: {}", path, message),
+            },
+            Self::RuntimeError {
+                token,
+                message,
+                backtrace,
+            } => Self::RuntimeError {
+                token,
+                message: format!("in module `{}`: {}", path, message),
+                backtrace,
+            },
+            Self::LoxError { line, message } => Self::LoxError {
+                line,
+                message: format!("in module `{}`: {}", path, message),
+            },
+            // 抛出的值可能被导入方捕获，保持不变，只记录最内层的模块
+            Self::Throw {
+                token,
+                value,
+                module: None,
+            } => Self::Throw {
+                token,
+                value,
+                module: Some(Rc::new(path.to_string())),
+            },
+            err => err,
+        }
+    }
+
+    pub fn interrupted(message: String) -> Self {
+        Self::Interrupted { message }
+    }
+
+    pub fn exit(code: i32) -> Self {
+        Self::Exit { code }
+    }
+
+    // 为运行时错误附加调用栈，已有调用栈时保持不变
+    pub fn with_backtrace(self, frames: Vec<CallFrame>) -> Self {
+        match self {
+            Self::RuntimeError {
+                token,
+                message,
+                backtrace,
+            } if backtrace.is_empty() => Self::RuntimeError {
+                token,
+                message,
+                backtrace: frames,
+            },
+            err => err,
+        }
     }
 
     pub fn return_error(value: Object) -> Self {
         Self::Return { value }
     }
 
+    // 可被 catch 捕获的错误转换为 Lox 中的值，其他情况原样返回
+    pub fn into_exception(self) -> Result<Object, LoxResult> {
+        match self {
+            Self::Throw { value, .. } => Ok(value),
+            Self::RuntimeError { token, message, .. } => Ok(Object::Error {
+                message,
+                line: token.line,
+            }),
+            Self::LoxError { line, message } => Ok(Object::Error { message, line }),
+            err => Err(err),
+        }
+    }
+
+    // 在顶层按错误种类报告
+    pub fn report_error(&self) {
+        let loc = match self {
+            Self::ParseError { .. } => "Parse Error",
+            Self::RuntimeError { .. } | Self::NativeError { .. } => "Runtime Error",
+            Self::LoxError { .. } => "Error",
+            Self::SystemError { .. } => "System Error",
+            Self::Interrupted { .. } => "Interrupted",
+            Self::Throw { .. } => "Uncaught Exception",
+            _ => "",
+        };
+        self.report(loc);
+    }
+
     pub fn report(&self, loc: &str) {
         match self {
             Self::LoxError { line, message } => {
                 eprintln!("[line: {line}], {loc}: {message}");
             }
-            Self::ParseError { token, message } | Self::RuntimeError { token, message } => {
+            Self::ParseError { token, message } | Self::RuntimeError { token, message, .. } => {
                 if token.is(crate::token_type::TokenType::Eof) {
                     eprintln!("[line: {} at end], {loc}: {message}", token.line);
                 } else {
@@ -68,8 +207,46 @@ impl LoxResult {
                     );
                 }
             }
-            Self::SystemError { message } => eprintln!("{loc}: {message}"),
+            Self::SystemError { message }
+            | Self::NativeError { message }
+            | Self::Interrupted { message } => {
+                eprintln!("{loc}: {message}")
+            }
+            Self::Throw {
+                token,
+                value,
+                module,
+            } => {
+                let module = module
+                    .as_ref()
+                    .map(|path| format!("in module `{}`: ", path))
+                    .unwrap_or_default();
+                eprintln!(
+                    "[line: {} at `{}`], {}: {}{}",
+                    token.line,
+                    token.as_string(),
+                    loc,
+                    module,
+                    value
+                );
+            }
             _ => (),
         }
+
+        // 最内层的调用在前，过长时省略中间部分
+        if let Self::RuntimeError { backtrace, .. } = self {
+            let len = backtrace.len();
+            for (i, frame) in backtrace.iter().rev().enumerate() {
+                if len > MAX_REPORT_FRAMES && i == MAX_REPORT_FRAMES / 2 {
+                    eprintln!("    ... {} more", len - MAX_REPORT_FRAMES);
+                }
+                if len <= MAX_REPORT_FRAMES
+                    || i < MAX_REPORT_FRAMES / 2
+                    || i >= len - MAX_REPORT_FRAMES / 2
+                {
+                    eprintln!("    {frame}");
+                }
+            }
+        }
     }
 }
@@ -1,5 +1,6 @@
 use crate::core::*;
 use crate::error::*;
+use crate::stmt::*;
 use crate::token::*;
 
 use std::rc::Rc;
@@ -14,6 +15,11 @@ pub enum Expr {
     Unary(UnaryExpr),
     Call(CallExpr),
     Variable(VariableExpr),
+    CompoundAssign(CompoundAssignExpr),
+    Update(UpdateExpr),
+    Conditional(ConditionalExpr),
+    Lambda(LambdaExpr),
+    Get(GetExpr),
 }
 
 impl Expr {
@@ -27,6 +33,11 @@ impl Expr {
             Expr::Unary(b) => b.accept(visitor),
             Expr::Call(b) => b.accept(visitor),
             Expr::Variable(b) => b.accept(visitor),
+            Expr::CompoundAssign(b) => b.accept(visitor),
+            Expr::Update(b) => b.accept(visitor),
+            Expr::Conditional(b) => b.accept(visitor),
+            Expr::Lambda(b) => b.accept(visitor),
+            Expr::Get(b) => b.accept(visitor),
         }
     }
 }
@@ -72,6 +83,7 @@ pub struct CallExpr {
     pub callee: Rc<Expr>,
     pub paren: Token,
     pub arguments: Vec<Rc<Expr>>,
+    pub names: Vec<Token>,
 }
 
 #[derive(Debug)]
@@ -79,6 +91,38 @@ pub struct VariableExpr {
     pub name: Token,
 }
 
+#[derive(Debug)]
+pub struct CompoundAssignExpr {
+    pub name: Token,
+    pub operator: Token,
+    pub value: Rc<Expr>,
+}
+
+#[derive(Debug)]
+pub struct UpdateExpr {
+    pub name: Token,
+    pub operator: Token,
+    pub prefix: bool,
+}
+
+#[derive(Debug)]
+pub struct ConditionalExpr {
+    pub condition: Rc<Expr>,
+    pub then_branch: Rc<Expr>,
+    pub else_branch: Rc<Expr>,
+}
+
+#[derive(Debug)]
+pub struct LambdaExpr {
+    pub declaration: Rc<FunctionStmt>,
+}
+
+#[derive(Debug)]
+pub struct GetExpr {
+    pub object: Rc<Expr>,
+    pub name: Token,
+}
+
 impl AssignExpr {
     pub fn accept<T>(&self, visitor: &dyn ExprVisitor<T>) -> Result<T, LoxResult> {
         visitor.visit_assign_expr(self)
@@ -127,6 +171,36 @@ impl VariableExpr {
     }
 }
 
+impl CompoundAssignExpr {
+    pub fn accept<T>(&self, visitor: &dyn ExprVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_compound_assign_expr(self)
+    }
+}
+
+impl UpdateExpr {
+    pub fn accept<T>(&self, visitor: &dyn ExprVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_update_expr(self)
+    }
+}
+
+impl ConditionalExpr {
+    pub fn accept<T>(&self, visitor: &dyn ExprVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_conditional_expr(self)
+    }
+}
+
+impl LambdaExpr {
+    pub fn accept<T>(&self, visitor: &dyn ExprVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_lambda_expr(self)
+    }
+}
+
+impl GetExpr {
+    pub fn accept<T>(&self, visitor: &dyn ExprVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_get_expr(self)
+    }
+}
+
 pub trait ExprVisitor<T> {
     fn visit_assign_expr(&self, expr: &AssignExpr) -> Result<T, LoxResult>;
     fn visit_binary_expr(&self, expr: &BinaryExpr) -> Result<T, LoxResult>;
@@ -136,4 +210,9 @@ pub trait ExprVisitor<T> {
     fn visit_unary_expr(&self, expr: &UnaryExpr) -> Result<T, LoxResult>;
     fn visit_call_expr(&self, expr: &CallExpr) -> Result<T, LoxResult>;
     fn visit_variable_expr(&self, expr: &VariableExpr) -> Result<T, LoxResult>;
+    fn visit_compound_assign_expr(&self, expr: &CompoundAssignExpr) -> Result<T, LoxResult>;
+    fn visit_update_expr(&self, expr: &UpdateExpr) -> Result<T, LoxResult>;
+    fn visit_conditional_expr(&self, expr: &ConditionalExpr) -> Result<T, LoxResult>;
+    fn visit_lambda_expr(&self, expr: &LambdaExpr) -> Result<T, LoxResult>;
+    fn visit_get_expr(&self, expr: &GetExpr) -> Result<T, LoxResult>;
 }
@@ -1,10 +1,13 @@
+use std::ops::Deref;
+use std::rc::Rc;
+
 use crate::core::*;
 use crate::error::LoxResult;
 use crate::expr::*;
 use crate::token::*;
 use crate::token_type::TokenType;
 
-use super::Interpreter;
+use super::{CallFrame, Interpreter, NumericPolicy};
 
 impl ExprVisitor<Object> for Interpreter {
     fn visit_binary_expr(&self, expr: &BinaryExpr) -> Result<Object, LoxResult> {
@@ -19,18 +22,7 @@ impl ExprVisitor<Object> for Interpreter {
         //     _ => Err(LoxError::error(0, "invalid number".to_string())),
         // }
 
-        match (left, right) {
-            (Object::Number(left_num), Object::Number(right_num)) => {
-                self.number_binary_evaluate(left_num, right_num, &expr.operator)
-            }
-            // (Object::Str(left), Object::Str(right)) => {},
-            (Object::Nil, Object::Nil) => Ok(Object::True),
-
-            _ => Err(LoxResult::runtime_error(
-                &expr.operator,
-                "interpreter error invalid number".to_string(),
-            )),
-        }
+        self.binary_evaluate(left, right, &expr.operator)
     }
 
     fn visit_grouping_expr(&self, expr: &GroupingExpr) -> Result<Object, LoxResult> {
@@ -51,10 +43,7 @@ impl ExprVisitor<Object> for Interpreter {
                     "invalid number".to_string(),
                 )),
             },
-            TokenType::Bang => match right {
-                Object::Nil | Object::False => Ok(Object::True),
-                _ => Ok(Object::False),
-            },
+            TokenType::Bang => Ok(Object::Bool(!self.is_truthy(&right))),
             _ => Err(LoxResult::runtime_error(
                 &expr.operator,
                 "invalid expression".to_string(),
@@ -77,11 +66,66 @@ impl ExprVisitor<Object> for Interpreter {
         Ok(value)
     }
 
+    fn visit_compound_assign_expr(&self, expr: &CompoundAssignExpr) -> Result<Object, LoxResult> {
+        // 目标只求值一次
+        let current = self.environment.borrow().borrow().get(&expr.name)?;
+        let value = self.evaluate(&expr.value)?;
+
+        let tk_type = match expr.operator.tk_type {
+            TokenType::PlusEqual => TokenType::Plus,
+            TokenType::MinusEqual => TokenType::Minus,
+            TokenType::StarEqual => TokenType::Star,
+            TokenType::SlashEqual => TokenType::Slash,
+            _ => {
+                return Err(LoxResult::runtime_error(
+                    &expr.operator,
+                    "invalid operator".to_string(),
+                ))
+            }
+        };
+        let operator = Token::new(tk_type, expr.operator.as_string(), None, expr.operator.line);
+        let value = self.binary_evaluate(current, value, &operator)?;
+        self.environment
+            .borrow()
+            .borrow_mut()
+            .assign(&expr.name, value.clone())?;
+
+        Ok(value)
+    }
+
+    fn visit_update_expr(&self, expr: &UpdateExpr) -> Result<Object, LoxResult> {
+        let current = match self.environment.borrow().borrow().get(&expr.name)? {
+            Object::Number(num) => num,
+            _ => {
+                return Err(LoxResult::runtime_error(
+                    &expr.operator,
+                    "invalid number".to_string(),
+                ))
+            }
+        };
+        let value = if expr.operator.is(TokenType::PlusPlus) {
+            current + 1.0
+        } else {
+            current - 1.0
+        };
+        self.environment
+            .borrow()
+            .borrow_mut()
+            .assign(&expr.name, Object::Number(value))?;
+
+        if expr.prefix {
+            Ok(Object::Number(value))
+        } else {
+            Ok(Object::Number(current))
+        }
+    }
+
     fn visit_logical_expr(&self, expr: &LogicalExpr) -> Result<Object, LoxResult> {
         let left = self.evaluate(&expr.left)?;
 
         if (expr.operator.is(TokenType::Or) && self.is_truthy(&left))
             || (expr.operator.is(TokenType::And) && !self.is_truthy(&left))
+            || (expr.operator.is(TokenType::QuestionQuestion) && left != Object::Nil)
         {
             Ok(left)
         } else {
@@ -89,7 +133,122 @@ impl ExprVisitor<Object> for Interpreter {
         }
     }
 
+    fn visit_conditional_expr(&self, expr: &ConditionalExpr) -> Result<Object, LoxResult> {
+        if self.is_truthy(&self.evaluate(&expr.condition)?) {
+            self.evaluate(&expr.then_branch)
+        } else {
+            self.evaluate(&expr.else_branch)
+        }
+    }
+
+    fn visit_lambda_expr(&self, expr: &LambdaExpr) -> Result<Object, LoxResult> {
+        let function = LoxFunction::new(&expr.declaration, self.environment.borrow().deref());
+        Ok(Object::Func(LoxCallable {
+            func: Rc::new(function),
+        }))
+    }
+
+    fn visit_get_expr(&self, expr: &GetExpr) -> Result<Object, LoxResult> {
+        match (self.evaluate(&expr.object)?, expr.name.lexeme.as_str()) {
+            (Object::Error { message, .. }, "message") => Ok(Object::Str(message)),
+            (Object::Error { line, .. }, "line") => Ok(Object::Number(line as f64)),
+            (Object::Foreign(foreign), name) => match self.method(foreign.type_name(), name) {
+                Some(method) => Ok(Object::Func(LoxCallable {
+                    func: Rc::new(BoundMethod::new(Object::Foreign(foreign), method)),
+                })),
+                None => Err(LoxResult::runtime_error(
+                    &expr.name,
+                    format!("undefined property `{}`", expr.name.lexeme),
+                )),
+            },
+            (Object::List(items), name) => match self.list_method(name) {
+                Some(method) => Ok(Object::Func(LoxCallable {
+                    func: Rc::new(BoundMethod::new(Object::List(items), method)),
+                })),
+                None => Err(LoxResult::runtime_error(
+                    &expr.name,
+                    format!("undefined property `{}`", expr.name.lexeme),
+                )),
+            },
+            (Object::Module(module), name) => module
+                .get(name)
+                .map_err(|message| LoxResult::runtime_error(&expr.name, message)),
+            (Object::Error { .. }, _) => Err(LoxResult::runtime_error(
+                &expr.name,
+                format!("undefined property `{}`", expr.name.lexeme),
+            )),
+            _ => Err(LoxResult::runtime_error(
+                &expr.name,
+                "only objects have properties".to_string(),
+            )),
+        }
+    }
+
     fn visit_call_expr(&self, expr: &CallExpr) -> Result<Object, LoxResult> {
+        let (f, arguments) = self.prepare_call(expr)?;
+        self.call_in_frame(&f, &expr.paren, arguments)
+    }
+}
+
+impl Interpreter {
+    // 压入调用栈帧后执行调用，超出调用深度时报 stack overflow
+    pub(super) fn call_in_frame(
+        &self,
+        f: &LoxCallable,
+        paren: &Token,
+        arguments: Vec<Option<Object>>,
+    ) -> Result<Object, LoxResult> {
+        self.push_frame(f, paren)?;
+        // 最内层的调用处附加完整的调用栈
+        let result = f
+            .call_named(self, arguments)
+            .map_err(|e| e.at_call(paren).with_backtrace(self.backtrace()));
+        self.pop_frame();
+
+        result
+    }
+
+    pub(crate) fn push_frame(&self, f: &LoxCallable, paren: &Token) -> Result<(), LoxResult> {
+        if self.frames.borrow().len() >= self.max_call_depth {
+            return Err(
+                LoxResult::runtime_error(paren, "stack overflow".to_string())
+                    .with_backtrace(self.backtrace()),
+            );
+        }
+
+        self.frames.borrow_mut().push(Self::call_frame(f, paren));
+
+        Ok(())
+    }
+
+    // 尾调用复用栈帧，替换为被调用的函数
+    pub(crate) fn replace_frame(&self, f: &LoxCallable, paren: &Token) {
+        if let Some(frame) = self.frames.borrow_mut().last_mut() {
+            *frame = Self::call_frame(f, paren);
+        }
+    }
+
+    pub(crate) fn pop_frame(&self) {
+        self.frames.borrow_mut().pop();
+    }
+
+    fn call_frame(f: &LoxCallable, paren: &Token) -> CallFrame {
+        let name = match f.as_lox_function() {
+            Some(function) => function.name().to_string(),
+            None => f.to_string(),
+        };
+
+        CallFrame {
+            name,
+            line: paren.line,
+        }
+    }
+
+    // 对被调用者及实参求值并完成参数检查，尾调用时只准备不执行
+    pub(super) fn prepare_call(
+        &self,
+        expr: &CallExpr,
+    ) -> Result<(LoxCallable, Vec<Option<Object>>), LoxResult> {
         let callee = self.evaluate(&expr.callee)?;
 
         let mut arguments: Vec<Object> = vec![];
@@ -98,14 +257,16 @@ impl ExprVisitor<Object> for Interpreter {
         }
 
         if let Object::Func(f) = callee {
-            if arguments.len() != f.func.arity() {
-                return Err(LoxResult::runtime_error(
-                    &expr.paren,
-                    format!("expect {} arguments but got {}", f.arity(), arguments.len()),
-                ));
+            self.check_capabilities(&f, &expr.paren)?;
+            if expr.names.is_empty() {
+                self.check_arity(&f, arguments.len(), &expr.paren)?;
+                Ok((f, arguments.into_iter().map(Some).collect()))
+            } else {
+                let count = arguments.len();
+                let arguments = self.bind_named_arguments(&f, expr, arguments)?;
+                self.check_arity(&f, count, &expr.paren)?;
+                Ok((f, arguments))
             }
-
-            f.call(self, arguments)
         } else {
             Err(LoxResult::runtime_error(
                 &expr.paren,
@@ -113,9 +274,119 @@ impl ExprVisitor<Object> for Interpreter {
             ))
         }
     }
-}
 
-impl Interpreter {
+    pub(super) fn check_capabilities(
+        &self,
+        f: &LoxCallable,
+        paren: &Token,
+    ) -> Result<(), LoxResult> {
+        match self.missing_capability(&f.capabilities()) {
+            Some(capability) => Err(LoxResult::runtime_error(
+                paren,
+                format!("{} requires capability `{}`", f, capability),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    pub(super) fn check_arity(
+        &self,
+        f: &LoxCallable,
+        count: usize,
+        paren: &Token,
+    ) -> Result<(), LoxResult> {
+        let (min, max) = (f.arity(), f.max_arity());
+        if count < min || max.is_some_and(|max| count > max) {
+            let expect = match max {
+                Some(max) if max == min => format!("{min}"),
+                Some(max) => format!("{min} to {max}"),
+                None => format!("at least {min}"),
+            };
+            return Err(LoxResult::runtime_error(
+                paren,
+                format!("expect {} arguments but got {}", expect, count),
+            ));
+        }
+
+        Ok(())
+    }
+
+    // 将命名参数按形参名放到对应位置，未传入的位置为 None
+    fn bind_named_arguments(
+        &self,
+        f: &LoxCallable,
+        expr: &CallExpr,
+        mut arguments: Vec<Object>,
+    ) -> Result<Vec<Option<Object>>, LoxResult> {
+        let params = f.param_names();
+        if params.is_empty() {
+            return Err(LoxResult::runtime_error(
+                &expr.names[0],
+                format!("{} does not accept named arguments", f),
+            ));
+        }
+
+        let named = arguments.split_off(arguments.len() - expr.names.len());
+        let mut slots: Vec<Option<Object>> = arguments.into_iter().map(Some).collect();
+        if slots.len() < params.len() {
+            slots.resize(params.len(), None);
+        }
+
+        for (name, value) in expr.names.iter().zip(named) {
+            match params.iter().position(|param| *param == name.lexeme) {
+                None => {
+                    return Err(LoxResult::runtime_error(
+                        name,
+                        format!("unknown argument `{}`", name.lexeme),
+                    ))
+                }
+                Some(index) if slots[index].is_some() => {
+                    return Err(LoxResult::runtime_error(
+                        name,
+                        format!("argument `{}` given more than once", name.lexeme),
+                    ))
+                }
+                Some(index) => slots[index] = Some(value),
+            }
+        }
+
+        // 必填参数位于形参列表前部
+        if let Some(index) = slots.iter().take(f.arity()).position(|x| x.is_none()) {
+            return Err(LoxResult::runtime_error(
+                &expr.paren,
+                format!("missing argument `{}`", params[index]),
+            ));
+        }
+        while let Some(None) = slots.last() {
+            slots.pop();
+        }
+
+        Ok(slots)
+    }
+
+    fn binary_evaluate(
+        &self,
+        left: Object,
+        right: Object,
+        operator: &Token,
+    ) -> Result<Object, LoxResult> {
+        match (left, right) {
+            (Object::Number(left_num), Object::Number(right_num)) => {
+                self.number_binary_evaluate(left_num, right_num, operator)
+            }
+            (Object::Str(left), Object::Str(right)) if operator.is(TokenType::Plus) => {
+                self.allocate(left.len() + right.len(), operator)?;
+                Ok(Object::Str(left + &right))
+            }
+            (Object::Nil, Object::Nil) => Ok(Object::Bool(true)),
+
+            _ => Err(LoxResult::runtime_error(
+                operator,
+                "interpreter error invalid number".to_string(),
+            )),
+        }
+    }
+
     fn number_binary_evaluate(
         &self,
         left_num: f64,
@@ -124,52 +395,24 @@ impl Interpreter {
     ) -> Result<Object, LoxResult> {
         // good idea rewrite std::ops::{ADD, SUB}  std::cmp::PartialOrd for Object
         match token.tk_type {
-            TokenType::Minus => Ok(Object::Number(left_num - right_num)),
-            TokenType::Plus => Ok(Object::Number(left_num + right_num)),
-            TokenType::Star => Ok(Object::Number(left_num * right_num)),
-            TokenType::Slash => Ok(Object::Number(left_num / right_num)),
-            TokenType::BangEqual => {
-                if left_num != right_num {
-                    Ok(Object::True)
-                } else {
-                    Ok(Object::False)
-                }
-            }
-            TokenType::EqualEqual => {
-                if left_num == right_num {
-                    Ok(Object::True)
-                } else {
-                    Ok(Object::False)
-                }
-            }
-            TokenType::Greater => {
-                if left_num > right_num {
-                    Ok(Object::True)
-                } else {
-                    Ok(Object::False)
-                }
-            }
-            TokenType::GreaterEqual => {
-                if left_num >= right_num {
-                    Ok(Object::True)
-                } else {
-                    Ok(Object::False)
-                }
-            }
-            TokenType::Less => {
-                if left_num < right_num {
-                    Ok(Object::True)
-                } else {
-                    Ok(Object::False)
-                }
-            }
-            TokenType::LessEqual => {
-                if left_num <= right_num {
-                    Ok(Object::True)
-                } else {
-                    Ok(Object::False)
+            TokenType::Minus => self.number_result(left_num - right_num, token),
+            TokenType::Plus => self.number_result(left_num + right_num, token),
+            TokenType::Star => self.number_result(left_num * right_num, token),
+            TokenType::Slash => {
+                if self.numeric_policy == NumericPolicy::Strict && right_num == 0.0 {
+                    return Err(LoxResult::runtime_error(
+                        token,
+                        "division by zero".to_string(),
+                    ));
                 }
+                self.number_result(left_num / right_num, token)
             }
+            TokenType::BangEqual => Ok(Object::Bool(left_num != right_num)),
+            TokenType::EqualEqual => Ok(Object::Bool(left_num == right_num)),
+            TokenType::Greater => Ok(Object::Bool(left_num > right_num)),
+            TokenType::GreaterEqual => Ok(Object::Bool(left_num >= right_num)),
+            TokenType::Less => Ok(Object::Bool(left_num < right_num)),
+            TokenType::LessEqual => Ok(Object::Bool(left_num <= right_num)),
 
             _ => Err(LoxResult::runtime_error(
                 token,
@@ -177,6 +420,18 @@ impl Interpreter {
             )),
         }
     }
+
+    // strict 模式下不允许运算结果为 NaN
+    fn number_result(&self, value: f64, token: &Token) -> Result<Object, LoxResult> {
+        if self.numeric_policy == NumericPolicy::Strict && value.is_nan() {
+            return Err(LoxResult::runtime_error(
+                token,
+                "operation produced NaN".to_string(),
+            ));
+        }
+
+        Ok(Object::Number(value))
+    }
 }
 
 #[cfg(test)]
@@ -185,12 +440,13 @@ mod test {
 
     use crate::{
         core::Object,
+        error::LoxResult,
         expr::{BinaryExpr, Expr},
         token::Token,
         token_type::TokenType,
     };
 
-    use super::Interpreter;
+    use super::{Interpreter, NumericPolicy};
 
     fn make_literal(value: Object) -> Rc<Expr> {
         Rc::new(Expr::Literal(crate::expr::LiteralExpr { value }))
@@ -216,11 +472,60 @@ mod test {
     #[test]
     fn test_comparison() {
         let operator = Token::new(TokenType::EqualEqual, "==".to_string(), None, 10);
-        let expected: Vec<Object> = vec![Object::False, Object::True, Object::False];
+        let expected: Vec<Object> =
+            vec![Object::Bool(false), Object::Bool(true), Object::Bool(false)];
         // expected.push(make_literal(value))
         test_binary_num(20.0, &operator, expected);
     }
 
+    fn divide(interpreter: &Interpreter, left: f64, right: f64) -> Option<Object> {
+        let operator = Token::new(TokenType::Slash, "/".to_string(), None, 10);
+        let expr = Expr::Binary(BinaryExpr {
+            left: make_literal(Object::Number(left)),
+            operator,
+            right: make_literal(Object::Number(right)),
+        });
+        interpreter.evaluate(&expr).ok()
+    }
+
+    #[test]
+    fn test_ieee_division_by_zero() {
+        let interpreter = Interpreter::new();
+        assert_eq!(
+            divide(&interpreter, 1.0, 0.0),
+            Some(Object::Number(f64::INFINITY))
+        );
+        match divide(&interpreter, 0.0, 0.0) {
+            Some(Object::Number(n)) => assert!(n.is_nan()),
+            other => panic!("expect NaN, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_strict_division_by_zero() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_numeric_policy(NumericPolicy::Strict);
+        assert_eq!(divide(&interpreter, 1.0, 0.0), None);
+        assert_eq!(divide(&interpreter, 0.0, 0.0), None);
+        assert_eq!(divide(&interpreter, 1.0, 4.0), Some(Object::Number(0.25)));
+    }
+
+    #[test]
+    fn test_strict_nan() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_numeric_policy(NumericPolicy::Strict);
+        let operator = Token::new(TokenType::Minus, "-".to_string(), None, 10);
+        let expr = Expr::Binary(BinaryExpr {
+            left: make_literal(Object::Number(f64::INFINITY)),
+            operator,
+            right: make_literal(Object::Number(f64::INFINITY)),
+        });
+        let res = interpreter.evaluate(&expr);
+        assert!(
+            matches!(res, Err(LoxResult::RuntimeError { ref message, .. }) if message == "operation produced NaN")
+        );
+    }
+
     fn test_binary_num(left: f64, operator: &Token, expected: Vec<Object>) {
         let num = vec![5.0, 20.0, 50.0];
         for (b, right) in expected.iter().zip(num) {
@@ -1,40 +1,377 @@
-use std::{cell::RefCell, rc::Rc};
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use std::{fmt, rc::Rc};
 
 use crate::core::*;
-use crate::native::NativeClock;
-use crate::{environment::Environment, error::LoxResult, expr::Expr, stmt::Stmt};
+use crate::native::math::Random;
+use crate::native::{self, NativeClock, NativeFunction};
+use crate::{
+    environment::Environment, error::LoxResult, expr::Expr, stmt::Stmt, token::Token,
+    token_type::TokenType,
+};
 
 mod expr_interpreter;
+mod module;
 mod stmt_interpreter;
 
-#[derive(Default)]
+// 数值运算策略
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum NumericPolicy {
+    // IEEE 754 语义，`1/0` 得到 inf，`0/0` 得到 NaN
+    #[default]
+    Ieee,
+    // 除零或产生 NaN 时抛出运行时错误
+    Strict,
+}
+
+// 默认的最大调用深度
+// 解释器递归执行，调试构建中每层调用约占 10~20KB 栈空间，默认值须在 2MB 的线程栈中报告
+// stack overflow 而不是崩溃；在更大的栈上运行时可用 set_max_call_depth 调高
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 64;
+
+// 调用栈帧：被调用函数名及调用处所在行
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallFrame {
+    pub name: String,
+    pub line: usize,
+}
+
+impl fmt::Display for CallFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at {} (line {})", self.name, self.line)
+    }
+}
+
 pub struct Interpreter {
     pub globals: Rc<RefCell<Environment>>,
+    builtins: Rc<RefCell<Environment>>,
     pub environment: RefCell<Rc<RefCell<Environment>>>,
+    numeric_policy: NumericPolicy,
+    frames: RefCell<Vec<CallFrame>>,
+    max_call_depth: usize,
+    // 执行预算：语句条数、运行时长以及宿主设置的取消标记
+    step_limit: Option<u64>,
+    steps: Cell<u64>,
+    time_limit: Option<Duration>,
+    deadline: Cell<Option<Instant>>,
+    cancelled: Arc<AtomicBool>,
+    // 内存预算：本次运行累计分配的字符串及列表字节数
+    memory_limit: Option<usize>,
+    allocated: Cell<usize>,
+    capabilities: Capabilities,
+    random: Random,
+    // 宿主为 Foreign 值注册的方法，按类型名称及方法名查找
+    methods: HashMap<String, HashMap<String, LoxCallable>>,
+    // 列表的方法，与 Foreign 的方法分开存放，避免与宿主类型名冲突
+    list_methods: HashMap<String, LoxCallable>,
+    // 正在执行的脚本路径，import 以其所在目录解析相对路径
+    script_path: RefCell<Option<PathBuf>>,
+    // 已加载的模块及正在加载的模块，用于缓存及检测循环导入
+    modules: RefCell<HashMap<PathBuf, Rc<Module>>>,
+    loading: RefCell<Vec<PathBuf>>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Interpreter {
     pub fn new() -> Self {
-        let globals = Rc::new(RefCell::new(Environment::new()));
-        globals.borrow_mut().define(
+        Self::with_capabilities(Capabilities::all())
+    }
+
+    // 只授予指定能力的解释器，调用未授权的本地函数时报运行时错误
+    pub fn with_capabilities(capabilities: Capabilities) -> Self {
+        // 本地函数及常量位于最外层且不可赋值，脚本及各模块的全局环境都以其为外层环境
+        let builtins = Rc::new(RefCell::new(Environment::new()));
+        builtins.borrow_mut().define_constant(
             "clock".to_owned(),
             Object::Func(LoxCallable {
                 func: Rc::new(NativeClock::new()),
             }),
         );
+        for (name, value) in native::math::constants() {
+            builtins
+                .borrow_mut()
+                .define_constant(name.to_owned(), value);
+        }
+        for native in native::string::natives()
+            .into_iter()
+            .chain(native::math::natives())
+            .chain(native::io::natives())
+            .chain(native::types::natives())
+            .chain(native::list::natives())
+            .chain(native::system::natives())
+        {
+            builtins.borrow_mut().define_constant(
+                native.name().to_owned(),
+                Object::Func(LoxCallable {
+                    func: Rc::new(native),
+                }),
+            );
+        }
+
+        let globals = Rc::new(RefCell::new(Environment::new_enclosing(Rc::clone(
+            &builtins,
+        ))));
 
         Self {
             environment: RefCell::new(Rc::clone(&globals)),
             globals,
+            builtins,
+            numeric_policy: NumericPolicy::default(),
+            frames: RefCell::new(Vec::new()),
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            step_limit: None,
+            steps: Cell::new(0),
+            time_limit: None,
+            deadline: Cell::new(None),
+            cancelled: Arc::new(AtomicBool::new(false)),
+            memory_limit: None,
+            allocated: Cell::new(0),
+            capabilities,
+            random: Random::from_time(),
+            methods: HashMap::new(),
+            list_methods: native::list::methods()
+                .into_iter()
+                .map(|method| {
+                    (
+                        method.name().to_string(),
+                        LoxCallable {
+                            func: Rc::new(method),
+                        },
+                    )
+                })
+                .collect(),
+            script_path: RefCell::new(None),
+            modules: RefCell::new(HashMap::new()),
+            loading: RefCell::new(Vec::new()),
         }
     }
-    pub fn interpreter(&self, statements: &Vec<Stmt>) {
+
+    pub fn set_numeric_policy(&mut self, policy: NumericPolicy) {
+        self.numeric_policy = policy;
+    }
+
+    pub fn numeric_policy(&self) -> NumericPolicy {
+        self.numeric_policy
+    }
+
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    // 所需能力中第一个未授予的能力
+    pub(crate) fn missing_capability(&self, required: &[Capability]) -> Option<Capability> {
+        required
+            .iter()
+            .copied()
+            .find(|capability| !self.capabilities.contains(*capability))
+    }
+
+    // 设置随机数种子，相同种子下 random/random_int 产生相同序列
+    pub fn set_random_seed(&mut self, seed: u64) {
+        self.random.seed(seed);
+    }
+
+    pub fn random(&self) -> &Random {
+        &self.random
+    }
+
+    // 为 type_name 类型的 Foreign 值注册方法，脚本中以 `value.name(...)` 调用
+    pub fn register_method<T, F>(
+        &mut self,
+        type_name: &str,
+        name: &'static str,
+        arity: usize,
+        method: F,
+    ) where
+        T: Any,
+        F: Fn(&Interpreter, &T, Vec<Object>) -> Result<Object, LoxResult> + 'static,
+    {
+        let function = NativeFunction::new(name, arity, move |interpreter, mut arguments| {
+            let receiver = arguments.remove(0);
+            match &receiver {
+                Object::Foreign(foreign) => match foreign.downcast_ref::<T>() {
+                    Some(value) => method(interpreter, value, arguments),
+                    None => Err(LoxResult::native_error(format!(
+                        "invalid receiver {}",
+                        foreign
+                    ))),
+                },
+                other => Err(LoxResult::native_error(format!(
+                    "invalid receiver {}",
+                    other.type_name()
+                ))),
+            }
+        });
+        self.methods
+            .entry(type_name.to_string())
+            .or_default()
+            .insert(
+                name.to_string(),
+                LoxCallable {
+                    func: Rc::new(function),
+                },
+            );
+    }
+
+    fn method(&self, type_name: &str, name: &str) -> Option<LoxCallable> {
+        self.methods.get(type_name)?.get(name).cloned()
+    }
+
+    fn list_method(&self, name: &str) -> Option<LoxCallable> {
+        self.list_methods.get(name).cloned()
+    }
+
+    pub fn set_max_call_depth(&mut self, depth: usize) {
+        self.max_call_depth = depth;
+    }
+
+    // 每次运行最多执行的语句条数
+    pub fn set_step_limit(&mut self, limit: Option<u64>) {
+        self.step_limit = limit;
+    }
+
+    // 每次运行最长的执行时间
+    pub fn set_time_limit(&mut self, limit: Option<Duration>) {
+        self.time_limit = limit;
+    }
+
+    // 取消标记，宿主可在其他线程中置为 true 以终止正在运行的脚本
+    // 标记不会在运行前自动清除，运行前已取消的脚本同样被终止，须调用 clear_cancel 才能再次运行
+    pub fn cancel_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.cancelled)
+    }
+
+    // 清除取消标记
+    pub fn clear_cancel(&self) {
+        self.cancelled.store(false, Ordering::Relaxed);
+    }
+
+    // 每次运行最多累计分配的字节数
+    pub fn set_memory_limit(&mut self, limit: Option<usize>) {
+        self.memory_limit = limit;
+    }
+
+    // 本次运行已累计分配的字节数
+    pub fn allocated(&self) -> usize {
+        self.allocated.get()
+    }
+
+    // 重新开始计算执行预算，每次运行前调用
+    pub fn reset_budget(&self) {
+        self.steps.set(0);
+        self.allocated.set(0);
+        self.deadline
+            .set(self.time_limit.map(|limit| Instant::now() + limit));
+    }
+
+    fn check_budget(&self) -> Result<(), LoxResult> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return Err(LoxResult::interrupted("script cancelled".to_string()));
+        }
+        if let Some(limit) = self.step_limit {
+            if self.steps.get() > limit {
+                return Err(LoxResult::interrupted(format!(
+                    "step limit of {limit} exceeded"
+                )));
+            }
+        }
+        if let Some(deadline) = self.deadline.get() {
+            if Instant::now() >= deadline {
+                return Err(LoxResult::interrupted("time limit exceeded".to_string()));
+            }
+        }
+
+        Ok(())
+    }
+
+    // 记录一次分配，超出内存预算时在 token 处报运行时错误
+    pub fn allocate(&self, bytes: usize, token: &Token) -> Result<(), LoxResult> {
+        self.reserve(bytes)
+            .map_err(|message| LoxResult::runtime_error(token, message))
+    }
+
+    // 记录一次分配，超出内存预算时返回错误信息，供本地函数使用
+    pub fn reserve(&self, bytes: usize) -> Result<(), String> {
+        let total = self.allocated.get().saturating_add(bytes);
+        if let Some(limit) = self.memory_limit {
+            if total > limit {
+                return Err(format!("memory limit of {limit} bytes exceeded"));
+            }
+        }
+        self.allocated.set(total);
+
+        Ok(())
+    }
+
+    // 归还预留但未使用的字节数
+    pub(crate) fn release(&self, bytes: usize) {
+        self.allocated
+            .set(self.allocated.get().saturating_sub(bytes));
+    }
+
+    // 距内存预算上限剩余的字节数，未设置上限时为 None
+    pub(crate) fn available_memory(&self) -> Option<usize> {
+        self.memory_limit
+            .map(|limit| limit.saturating_sub(self.allocated.get()))
+    }
+
+    // 当前的调用栈，最内层在最后
+    pub fn backtrace(&self) -> Vec<CallFrame> {
+        self.frames.borrow().clone()
+    }
+    // 脚本调用 exit 时停止执行并返回退出码
+    pub fn interpreter(&self, statements: &Vec<Stmt>) -> Option<i32> {
+        self.reset_budget();
         for stmt in statements {
             match self.execute(stmt) {
                 Ok(_) => (),
-                Err(e) => e.report(""),
+                Err(LoxResult::Exit { code }) => return Some(code),
+                Err(e @ LoxResult::Interrupted { .. }) => {
+                    e.report_error();
+                    return None;
+                }
+                Err(e) => e.report_error(),
             }
         }
+        None
+    }
+
+    // 按名称调用脚本中定义的全局函数，供宿主将脚本作为插件使用
+    pub fn call_function(&self, name: &str, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+        let token = Token::new(TokenType::Identifier, name.to_string(), None, 0);
+        let f = match self.globals.borrow().lookup(name) {
+            Some(Object::Func(f)) => f,
+            Some(other) => {
+                return Err(LoxResult::runtime_error(
+                    &token,
+                    format!("`{}` is a {}, not a function", name, other.type_name()),
+                ))
+            }
+            None => {
+                return Err(LoxResult::runtime_error(
+                    &token,
+                    format!("undefined function `{}`", name),
+                ))
+            }
+        };
+        self.check_capabilities(&f, &token)?;
+        self.check_arity(&f, arguments.len(), &token)?;
+
+        // 只在宿主发起的最外层调用时重置预算，本地函数回调时沿用当前预算
+        if self.frames.borrow().is_empty() {
+            self.reset_budget();
+        }
+        self.call_in_frame(&f, &token, arguments.into_iter().map(Some).collect())
     }
 
     pub fn print_environment(&self) {
@@ -42,16 +379,13 @@ impl Interpreter {
     }
 
     fn is_truthy(&self, obj: &Object) -> bool {
-        // match obj {
-        //     Object::False | Object::Nil => false,
-        //     _ => true,
-        // }
-
-        !matches!(obj, Object::False | Object::Nil)
+        !matches!(obj, Object::Bool(false) | Object::Nil)
     }
 
     // 语句执行器
     pub fn execute(&self, stmt: &Stmt) -> Result<(), LoxResult> {
+        self.steps.set(self.steps.get() + 1);
+        self.check_budget()?;
         stmt.accept(self)
     }
 
@@ -60,13 +394,25 @@ impl Interpreter {
         expr.accept(self)
     }
 
+    // 在指定环境中对表达式求值
+    pub fn evaluate_in(
+        &self,
+        expr: &Expr,
+        environment: Rc<RefCell<Environment>>,
+    ) -> Result<Object, LoxResult> {
+        let previous = self.environment.replace(environment);
+        let result = self.evaluate(expr);
+        self.environment.replace(previous);
+
+        result
+    }
+
     pub fn execute_block(
         &self,
         statements: &[Rc<Stmt>],
-        environment: Environment,
+        environment: Rc<RefCell<Environment>>,
     ) -> Result<(), LoxResult> {
-        let e = Rc::new(RefCell::new(environment));
-        let previous = self.environment.replace(e);
+        let previous = self.environment.replace(environment);
 
         // try catch
         let result = statements.iter().try_for_each(|stmt| self.execute(stmt));
@@ -0,0 +1,232 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::core::{Capability, Module};
+use crate::environment::Environment;
+use crate::error::LoxResult;
+use crate::parser::Parser;
+use crate::resolver::Resolver;
+use crate::scanner::Scanner;
+use crate::stmt::Stmt;
+use crate::token::Token;
+
+use super::Interpreter;
+
+impl Interpreter {
+    // 设置正在执行的脚本路径
+    pub fn set_script_path(&self, path: impl AsRef<Path>) {
+        self.script_path.replace(Some(path.as_ref().to_path_buf()));
+    }
+
+    // 加载模块，每个模块只执行一次，之后的导入直接返回缓存
+    pub(super) fn import_module(
+        &self,
+        keyword: &Token,
+        path: &str,
+    ) -> Result<Rc<Module>, LoxResult> {
+        if !self.capabilities.contains(Capability::FileRead) {
+            return Err(LoxResult::runtime_error(
+                keyword,
+                format!("import requires capability `{}`", Capability::FileRead),
+            ));
+        }
+
+        let resolved = fs::canonicalize(self.resolve_module_path(path)).map_err(|e| {
+            LoxResult::runtime_error(keyword, format!("cannot import `{}`: {}", path, e))
+        })?;
+        if let Some(module) = self.modules.borrow().get(&resolved) {
+            return Ok(Rc::clone(module));
+        }
+        if let Some(start) = self.loading.borrow().iter().position(|p| *p == resolved) {
+            let cycle: Vec<String> = self.loading.borrow()[start..]
+                .iter()
+                .chain(Some(&resolved))
+                .map(|p| p.display().to_string())
+                .collect();
+            return Err(LoxResult::runtime_error(
+                keyword,
+                format!("import cycle: {}", cycle.join(" -> ")),
+            ));
+        }
+
+        let source = fs::read_to_string(&resolved).map_err(|e| {
+            LoxResult::runtime_error(keyword, format!("cannot import `{}`: {}", path, e))
+        })?;
+
+        // 模块拥有独立的全局环境，只能访问本地函数
+        let environment = Rc::new(RefCell::new(Environment::new_enclosing(Rc::clone(
+            &self.builtins,
+        ))));
+        self.loading.borrow_mut().push(resolved.clone());
+        let previous = self.script_path.replace(Some(resolved.clone()));
+        let result = self.run_module(source, Rc::clone(&environment));
+        self.script_path.replace(previous);
+        self.loading.borrow_mut().pop();
+        let exports = result.map_err(|e| e.in_module(&resolved.display().to_string()))?;
+
+        let module = Rc::new(Module::new(path.to_string(), environment, exports));
+        self.modules
+            .borrow_mut()
+            .insert(resolved, Rc::clone(&module));
+
+        Ok(module)
+    }
+
+    // 相对路径以导入者所在目录为基准
+    fn resolve_module_path(&self, path: &str) -> PathBuf {
+        match self.script_path.borrow().as_ref().and_then(|p| p.parent()) {
+            Some(dir) => dir.join(path),
+            None => PathBuf::from(path),
+        }
+    }
+
+    // 执行模块，返回导出的名称
+    fn run_module(
+        &self,
+        source: String,
+        environment: Rc<RefCell<Environment>>,
+    ) -> Result<HashSet<String>, LoxResult> {
+        let mut scanner = Scanner::new(source);
+        let tokens = scanner.scan_tokens()?;
+        let mut parser = Parser::new(tokens);
+        let statements: Vec<Rc<Stmt>> = parser.parse()?.into_iter().map(Rc::new).collect();
+        Resolver::default().resolve(&statements)?;
+
+        match self.execute_block(&statements, environment) {
+            // 模块顶层的 return 提前结束模块
+            Ok(_) | Err(LoxResult::Return { .. }) => (),
+            Err(e) => return Err(e),
+        }
+
+        let exports = statements
+            .iter()
+            .filter_map(|stmt| match stmt.as_ref() {
+                Stmt::Export(export) => match export.declaration.as_ref() {
+                    Stmt::Var(var) => Some(var.name.as_string()),
+                    Stmt::Function(function) => Some(function.name.as_string()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+
+        Ok(exports)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use crate::core::{Capabilities, Object};
+    use crate::error::LoxResult;
+    use crate::interpreter::Interpreter;
+    use crate::test_util::{get, run};
+
+    // 在临时目录中写入模块文件，返回目录
+    fn write_modules(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("lox-{}-{}", name, std::process::id()));
+        for (path, source) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, source).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_import() {
+        let dir = write_modules(
+            "import",
+            &[
+                ("lib/geometry.lox", "from \"util.lox\" import square; export func area(r) { return PI * square(r); } export var name = \"geometry\";"),
+                ("lib/util.lox", "export func square(x) { return x * x; }"),
+            ],
+        );
+        let interpreter = Interpreter::new();
+        interpreter.set_script_path(dir.join("main.lox"));
+        let result = run(
+            &interpreter,
+            "import \"lib/geometry.lox\" as g; import \"lib/geometry.lox\" as h;
+             from \"lib/util.lox\" import square;
+             var a = g.area(1); var n = g.name; var s = square(3);",
+        );
+        fs::remove_dir_all(&dir).unwrap();
+        result.unwrap();
+
+        assert_eq!(get(&interpreter, "a"), Object::Number(std::f64::consts::PI));
+        assert_eq!(get(&interpreter, "n"), Object::Str("geometry".to_string()));
+        assert_eq!(get(&interpreter, "s"), Object::Number(9.0));
+        // 同一模块只加载一次
+        assert_eq!(get(&interpreter, "g"), get(&interpreter, "h"));
+    }
+
+    #[test]
+    fn test_import_errors() {
+        let dir = write_modules(
+            "import-errors",
+            &[
+                ("a.lox", "import \"b.lox\" as b;"),
+                ("b.lox", "import \"a.lox\" as a;"),
+                ("bad.lox", "var x = 1;\nvar y = -\"x\";"),
+                ("ok.lox", "export var x = 1; var hidden = 2;"),
+                ("nested.lox", "{ export var x = 1; }"),
+                ("throws.lox", "throw \"boom\";"),
+            ],
+        );
+        let interpreter = Interpreter::new();
+        interpreter.set_script_path(dir.join("main.lox"));
+        let message = |source: &str| match run(&interpreter, source) {
+            Err(LoxResult::RuntimeError { message, token, .. }) => (message, token.line),
+            other => panic!("expect runtime error, got {:?}", other),
+        };
+
+        let (cycle, _) = message("import \"a.lox\" as a;");
+        let (bad, line) = message("import \"bad.lox\" as bad;");
+        let (missing, _) = message("from \"ok.lox\" import y;");
+        let (member, _) = message("import \"ok.lox\" as ok; ok.y;");
+        let (not_found, _) = message("import \"missing.lox\" as m;");
+        let (private, _) = message("import \"ok.lox\" as ok; ok.hidden;");
+        let (private_from, _) = message("from \"ok.lox\" import x, hidden;");
+        let nested = run(&interpreter, "import \"nested.lox\" as nested;");
+        let thrown = run(&interpreter, "import \"throws.lox\" as t;");
+        let caught = run(
+            &interpreter,
+            "var caught; try { import \"throws.lox\" as t; } catch (e) { caught = e; }",
+        );
+        let sandboxed = Interpreter::with_capabilities(Capabilities::none());
+        let denied = run(&sandboxed, "import \"ok.lox\" as ok;");
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(cycle.contains("b.lox`: import cycle: "));
+        assert!(cycle.contains("a.lox -> ") && cycle.ends_with("a.lox"));
+        assert!(bad.starts_with("in module `") && bad.ends_with("bad.lox`: invalid number"));
+        assert_eq!(line, 2);
+        assert_eq!(missing, "module `ok.lox` has no member `y`");
+        assert_eq!(member, "module `ok.lox` has no member `y`");
+        assert!(not_found.starts_with("cannot import `missing.lox`"));
+        assert_eq!(private, "`hidden` is not exported by module `ok.lox`");
+        assert_eq!(private_from, "`hidden` is not exported by module `ok.lox`");
+        assert!(matches!(
+            nested,
+            Err(LoxResult::ParseError { message, .. })
+                if message.ends_with("nested.lox`: `export` is only allowed at the top level")
+        ));
+        // 未捕获的异常记录所在模块，导入方仍可捕获原值
+        assert!(matches!(
+            thrown,
+            Err(LoxResult::Throw { value: Object::Str(s), module: Some(path), .. })
+                if s == "boom" && path.ends_with("throws.lox")
+        ));
+        caught.unwrap();
+        assert_eq!(get(&interpreter, "caught"), Object::Str("boom".to_string()));
+        assert!(matches!(
+            denied,
+            Err(LoxResult::RuntimeError { message, .. }) if message == "import requires capability `file_read`"
+        ));
+    }
+}
@@ -1,12 +1,15 @@
 // use std::rc::Rc;
 
+use std::cell::RefCell;
 use std::ops::Deref;
 use std::rc::Rc;
 
 use crate::core::{LoxCallable, LoxFunction, Object};
 use crate::environment::Environment;
 use crate::error::LoxResult;
+use crate::expr::Expr;
 use crate::stmt::*;
+use crate::token::Token;
 
 use super::Interpreter;
 
@@ -28,18 +31,17 @@ impl StmtVisitor<()> for Interpreter {
         } else {
             Object::Nil
         };
-        self.environment
-            .borrow()
-            .borrow_mut()
-            .define(stmt.name.lexeme.clone(), value);
-        Ok(())
+        self.declare(&stmt.name, value, stmt.constant)
     }
 
     fn visit_block_stmt(&self, stmt: &BlockStmt) -> Result<(), LoxResult> {
         // println!("{:?}", self.environment);
         // Rc::clone(self.environment.as_ref())
         let e = self.environment.borrow().clone();
-        self.execute_block(&stmt.statements, Environment::new_enclosing(e))?;
+        self.execute_block(
+            &stmt.statements,
+            Rc::new(RefCell::new(Environment::new_enclosing(e))),
+        )?;
         Ok(())
     }
 
@@ -55,6 +57,7 @@ impl StmtVisitor<()> for Interpreter {
 
     fn visit_while_stmt(&self, stmt: &WhileStmt) -> Result<(), LoxResult> {
         while self.is_truthy(&self.evaluate(&stmt.condition)?) {
+            self.check_budget()?;
             match self.execute(&stmt.body) {
                 Err(LoxResult::Break) => break,
                 Err(e) => return Err(e),
@@ -65,23 +68,116 @@ impl StmtVisitor<()> for Interpreter {
         Ok(())
     }
 
+    fn visit_for_stmt(&self, stmt: &ForStmt) -> Result<(), LoxResult> {
+        let mut environment = self.new_scope();
+        if let Some(ref initializer) = stmt.initializer {
+            self.execute_block(std::slice::from_ref(initializer), environment.clone())?;
+        }
+
+        loop {
+            if let Some(ref condition) = stmt.condition {
+                let condition = self.evaluate_in(condition, environment.clone())?;
+                if !self.is_truthy(&condition) {
+                    break;
+                }
+            }
+            self.check_budget()?;
+            match self.execute_block(std::slice::from_ref(&stmt.body), environment.clone()) {
+                Err(LoxResult::Break) => break,
+                Err(e) => return Err(e),
+                Ok(_) => (),
+            }
+
+            // 每次迭代使用新的环境，循环体中创建的闭包各自捕获当次迭代的值
+            let next = environment.borrow().copy();
+            environment = Rc::new(RefCell::new(next));
+            if let Some(ref increment) = stmt.increment {
+                self.evaluate_in(increment, environment.clone())?;
+            }
+        }
+
+        Ok(())
+    }
+
     fn visit_break_stmt(&self, _stmt: &BreakStmt) -> Result<(), LoxResult> {
         Err(LoxResult::Break)
     }
 
     fn visit_function_stmt(&self, stmt: &FunctionStmt) -> Result<(), LoxResult> {
         let function = LoxFunction::new(stmt, self.environment.borrow().deref());
-        self.environment.borrow().borrow_mut().define(
-            stmt.name.as_string(),
+        self.declare(
+            &stmt.name,
             Object::Func(LoxCallable {
                 func: Rc::new(function),
             }),
-        );
+            false,
+        )
+    }
+
+    fn visit_throw_stmt(&self, stmt: &ThrowStmt) -> Result<(), LoxResult> {
+        let value = self.evaluate(&stmt.value)?;
+        Err(LoxResult::Throw {
+            token: stmt.keyword.clone(),
+            value,
+            module: None,
+        })
+    }
+
+    fn visit_try_stmt(&self, stmt: &TryStmt) -> Result<(), LoxResult> {
+        let result = self.execute_block(&stmt.body, self.new_scope());
+        let result = self.finish_tail_call(result);
+
+        let result = match (result, &stmt.catch_name, &stmt.catch_body) {
+            (Err(e), Some(name), Some(catch_body)) => match e.into_exception() {
+                Ok(value) => {
+                    let environment = self.new_scope();
+                    environment.borrow_mut().define(name.as_string(), value);
+                    let result = self.execute_block(catch_body, environment);
+                    self.finish_tail_call(result)
+                }
+                Err(e) => Err(e),
+            },
+            (result, _, _) => result,
+        };
+
+        // finally 总会执行，其中的 return/break/异常 会覆盖之前的结果
+        if let Some(ref finally_body) = stmt.finally_body {
+            self.execute_block(finally_body, self.new_scope())?;
+        }
+
+        result
+    }
+
+    fn visit_export_stmt(&self, stmt: &ExportStmt) -> Result<(), LoxResult> {
+        self.execute(&stmt.declaration)
+    }
+
+    fn visit_import_stmt(&self, stmt: &ImportStmt) -> Result<(), LoxResult> {
+        let module = self.import_module(&stmt.keyword, &stmt.path)?;
+
+        for name in stmt.names.iter() {
+            let value = module
+                .get(&name.lexeme)
+                .map_err(|message| LoxResult::runtime_error(name, message))?;
+            self.declare(name, value, false)?;
+        }
+        if let Some(ref alias) = stmt.alias {
+            self.declare(alias, Object::Module(module), false)?;
+        }
 
         Ok(())
     }
 
     fn visit_return_stmt(&self, stmt: &ReturnStmt) -> Result<(), LoxResult> {
+        if let (true, Some(Expr::Call(call))) = (stmt.tail_call, stmt.value.as_deref()) {
+            let (callee, arguments) = self.prepare_call(call)?;
+            return Err(LoxResult::TailCall {
+                callee,
+                paren: call.paren.clone(),
+                arguments,
+            });
+        }
+
         let value = if let Some(ref expr) = stmt.value {
             self.evaluate(expr)?
         } else {
@@ -92,5 +188,1112 @@ impl StmtVisitor<()> for Interpreter {
     }
 }
 
+impl Interpreter {
+    // 在当前作用域中声明，同一作用域中不能重新声明常量，内层作用域可以遮蔽
+    // 脚本及模块的顶层不能重新声明本地函数等内置名称
+    fn declare(&self, name: &Token, value: Object, constant: bool) -> Result<(), LoxResult> {
+        let environment = self.environment.borrow();
+        if environment.borrow().is_constant(&name.lexeme) {
+            return Err(LoxResult::runtime_error(
+                name,
+                format!("cannot redeclare constant `{}`", name.lexeme),
+            ));
+        }
+        if environment.borrow().is_enclosed_by(&self.builtins)
+            && self.builtins.borrow().is_constant(&name.lexeme)
+        {
+            return Err(LoxResult::runtime_error(
+                name,
+                format!("cannot redeclare builtin `{}`", name.lexeme),
+            ));
+        }
+
+        if constant {
+            environment
+                .borrow_mut()
+                .define_constant(name.as_string(), value);
+        } else {
+            environment.borrow_mut().define(name.as_string(), value);
+        }
+
+        Ok(())
+    }
+
+    fn new_scope(&self) -> Rc<RefCell<Environment>> {
+        let e = self.environment.borrow().clone();
+        Rc::new(RefCell::new(Environment::new_enclosing(e)))
+    }
+
+    // try 中的尾调用须在当前位置执行，其中的异常才能被捕获
+    fn finish_tail_call(&self, result: Result<(), LoxResult>) -> Result<(), LoxResult> {
+        match result {
+            Err(LoxResult::TailCall {
+                callee,
+                paren,
+                arguments,
+            }) => {
+                let value = self.call_in_frame(&callee, &paren, arguments)?;
+                Err(LoxResult::return_error(value))
+            }
+            result => result,
+        }
+    }
+}
+
 #[cfg(test)]
-mod test {}
+mod test {
+    use std::rc::Rc;
+
+    use crate::core::{
+        BoundMethod, Callable, Capabilities, Capability, Foreign, LoxCallable, Object,
+    };
+    use crate::error::LoxResult;
+    use crate::interpreter::{Interpreter, DEFAULT_MAX_CALL_DEPTH};
+    use crate::native::NativeFunction;
+    use crate::parser::Parser;
+    use crate::scanner::Scanner;
+    use crate::stmt::Stmt;
+    use crate::test_util::{get, run};
+
+    #[test]
+    fn test_compound_assign() {
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "var a = 10; a += 5; a -= 3; a *= 2; a /= 4; var b = 1; var c = b += 2;",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "a"), Object::Number(6.0));
+        assert_eq!(get(&interpreter, "b"), Object::Number(3.0));
+        assert_eq!(get(&interpreter, "c"), Object::Number(3.0));
+    }
+
+    #[test]
+    fn test_increment_decrement() {
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "var i = 1; var a = i++; var b = ++i; var c = i--; var d = --i;",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "a"), Object::Number(1.0));
+        assert_eq!(get(&interpreter, "b"), Object::Number(3.0));
+        assert_eq!(get(&interpreter, "c"), Object::Number(3.0));
+        assert_eq!(get(&interpreter, "d"), Object::Number(1.0));
+        assert_eq!(get(&interpreter, "i"), Object::Number(1.0));
+    }
+
+    #[test]
+    fn test_invalid_update_target() {
+        let interpreter = Interpreter::new();
+        assert!(run(&interpreter, "1++;").is_err());
+        assert!(run(&interpreter, "var a = 1; (a) += 1;").is_err());
+    }
+
+    #[test]
+    fn test_conditional() {
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "var a = 1 < 2 ? 10 : 20; var b = nil ? 10 : false ? 20 : 30; var c = 1 == 1 ? 1 : 2;",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "a"), Object::Number(10.0));
+        assert_eq!(get(&interpreter, "b"), Object::Number(30.0));
+        assert_eq!(get(&interpreter, "c"), Object::Number(1.0));
+    }
+
+    #[test]
+    fn test_conditional_short_circuit() {
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "var n = 0; var a = true ? n += 1 : (n += 10); var b = false ? (n += 100) : n;",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "a"), Object::Number(1.0));
+        assert_eq!(get(&interpreter, "b"), Object::Number(1.0));
+    }
+
+    #[test]
+    fn test_null_coalescing() {
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "var n = 0; var a = nil ?? 1; var b = false ?? 2; var c = 0 ?? (n += 1); var d = nil ?? nil ?? 3; var e = nil ?? false ? 4 : 5;",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "a"), Object::Number(1.0));
+        assert_eq!(get(&interpreter, "b"), Object::Bool(false));
+        assert_eq!(get(&interpreter, "c"), Object::Number(0.0));
+        assert_eq!(get(&interpreter, "d"), Object::Number(3.0));
+        assert_eq!(get(&interpreter, "e"), Object::Number(5.0));
+        assert_eq!(get(&interpreter, "n"), Object::Number(0.0));
+    }
+
+    #[test]
+    fn test_lambda() {
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "var add = func (a, b) { return a + b; }; var a = add(1, 2); var b = func () { return 7; }();",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "a"), Object::Number(3.0));
+        assert_eq!(get(&interpreter, "b"), Object::Number(7.0));
+    }
+
+    #[test]
+    fn test_arrow_function() {
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "var double = (a) => a * 2; var none = () => { return 1; }; var a = double(4); var b = none(); var c = (1 + 2) * 3;",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "a"), Object::Number(8.0));
+        assert_eq!(get(&interpreter, "b"), Object::Number(1.0));
+        assert_eq!(get(&interpreter, "c"), Object::Number(9.0));
+
+        // 分组与箭头函数互相嵌套
+        run(
+            &interpreter,
+            "var d = ((x) => ((x) + (1)))(((2))); var e = (((a, b) => a * b))((3), 4);",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "d"), Object::Number(3.0));
+        assert_eq!(get(&interpreter, "e"), Object::Number(12.0));
+    }
+
+    #[test]
+    fn test_lambda_closure() {
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "func apply(f, x) { return f(x); } func adder(n) { return (x) => x + n; } var a = apply(adder(10), 5);",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "a"), Object::Number(15.0));
+    }
+
+    #[test]
+    fn test_default_params() {
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "func f(a, b = 2, c = a + b) { return a * 100 + b * 10 + c; } var x = f(1); var y = f(1, 5); var z = f(1, 5, 0);",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "x"), Object::Number(123.0));
+        assert_eq!(get(&interpreter, "y"), Object::Number(156.0));
+        assert_eq!(get(&interpreter, "z"), Object::Number(150.0));
+    }
+
+    #[test]
+    fn test_default_evaluated_per_call() {
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "var n = 0; func next() { n += 1; return n; } func f(a = next()) { return a; } f(); f(); var x = f();",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "x"), Object::Number(3.0));
+    }
+
+    #[test]
+    fn test_rest_params() {
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "func f(a, ...rest) { return rest; } var x = f(1); var y = f(1, 2, 3); var g = (...all) => all; var z = g();",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "x"), Object::new_list(vec![]));
+        assert_eq!(
+            get(&interpreter, "y"),
+            Object::new_list(vec![Object::Number(2.0), Object::Number(3.0)])
+        );
+        assert_eq!(get(&interpreter, "z"), Object::new_list(vec![]));
+        assert_eq!(get(&interpreter, "y").to_string(), "[2, 3]");
+    }
+
+    #[test]
+    fn test_arity_errors() {
+        let interpreter = Interpreter::new();
+        run(&interpreter, "func f(a, b = 2) {} func g(a, ...rest) {}").unwrap();
+        for (source, expect) in [
+            ("f();", "expect 1 to 2 arguments but got 0"),
+            ("f(1, 2, 3);", "expect 1 to 2 arguments but got 3"),
+            ("g();", "expect at least 1 arguments but got 0"),
+        ] {
+            match run(&interpreter, source) {
+                Err(LoxResult::RuntimeError { message, .. }) => assert_eq!(message, expect),
+                other => panic!("expect runtime error, got {:?}", other),
+            }
+        }
+        assert!(run(&interpreter, "func h(a = 1, b) {}").is_err());
+        assert!(run(&interpreter, "func h(...a, b) {}").is_err());
+    }
+
+    #[test]
+    fn test_named_arguments() {
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "func f(a, b = 2, c = 3) { return a * 100 + b * 10 + c; } var x = f(b: 5, a: 1); var y = f(1, c: 9); var z = f(c: 0, a: 4);",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "x"), Object::Number(153.0));
+        assert_eq!(get(&interpreter, "y"), Object::Number(129.0));
+        assert_eq!(get(&interpreter, "z"), Object::Number(420.0));
+    }
+
+    #[test]
+    fn test_named_argument_errors() {
+        let interpreter = Interpreter::new();
+        run(&interpreter, "func f(a, b = 2) {}").unwrap();
+        for (source, expect) in [
+            ("f(a: 1, d: 2);", "unknown argument `d`"),
+            ("f(1, a: 2);", "argument `a` given more than once"),
+            ("f(b: 2);", "missing argument `a`"),
+            (
+                "clock(a: 1);",
+                "<native fn clock> does not accept named arguments",
+            ),
+        ] {
+            match run(&interpreter, source) {
+                Err(LoxResult::RuntimeError { message, .. }) => assert_eq!(message, expect),
+                other => panic!("expect runtime error, got {:?}", other),
+            }
+        }
+        for source in ["f(a: 1, 2);", "f(a: 1, a: 2);"] {
+            assert!(matches!(
+                run(&interpreter, source),
+                Err(LoxResult::ParseError { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_native_named_arguments() {
+        struct NativeSub;
+
+        impl std::fmt::Display for NativeSub {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "<native fn sub>")
+            }
+        }
+
+        impl Callable for NativeSub {
+            fn arity(&self) -> usize {
+                2
+            }
+
+            fn param_names(&self) -> Vec<String> {
+                vec!["a".to_string(), "b".to_string()]
+            }
+
+            fn call(
+                &self,
+                _interpreter: &Interpreter,
+                arguments: Vec<Object>,
+            ) -> Result<Object, LoxResult> {
+                match (&arguments[0], &arguments[1]) {
+                    (Object::Number(a), Object::Number(b)) => Ok(Object::Number(a - b)),
+                    _ => Ok(Object::Nil),
+                }
+            }
+        }
+
+        let interpreter = Interpreter::new();
+        interpreter.globals.borrow_mut().define(
+            "sub".to_string(),
+            Object::Func(LoxCallable {
+                func: Rc::new(NativeSub),
+            }),
+        );
+        run(&interpreter, "var x = sub(b: 1, a: 10);").unwrap();
+        assert_eq!(get(&interpreter, "x"), Object::Number(9.0));
+    }
+
+    #[test]
+    fn test_builtin_named_arguments() {
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "var a = substr(\"hello\", start: 1); var b = substr(end: 2, s: \"hello\", start: 0);
+             var c = replace(new: \"-\", old: \" \", s: \"a b c\"); var d = pow(exponent: 3, base: 2);",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "a"), Object::Str("ello".to_string()));
+        assert_eq!(get(&interpreter, "b"), Object::Str("he".to_string()));
+        assert_eq!(get(&interpreter, "c"), Object::Str("a-b-c".to_string()));
+        assert_eq!(get(&interpreter, "d"), Object::Number(8.0));
+
+        assert!(matches!(
+            run(&interpreter, "substr(\"hello\", begin: 1);"),
+            Err(LoxResult::RuntimeError { message, .. }) if message == "unknown argument `begin`"
+        ));
+        // 未声明形参名的本地函数不接受命名参数
+        assert!(run(&interpreter, "upper(s: \"a\");").is_err());
+    }
+
+    #[test]
+    fn test_tail_call() {
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "func count(n, acc) { if (n == 0) return acc; return count(n - 1, acc + 1); } var x = count(100000, 0);",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "x"), Object::Number(100000.0));
+    }
+
+    #[test]
+    fn test_mutual_tail_call() {
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "func even(n) { if (n == 0) return true; return odd(n - 1); }
+             func odd(n) { if (n == 0) return false; return even(n - 1); }
+             var x = even(100001);
+             var down = (n) => { if (n == 0) return 0; return down(n - 1); };
+             var y = down(100000);",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "x"), Object::Bool(false));
+        assert_eq!(get(&interpreter, "y"), Object::Number(0.0));
+    }
+
+    #[test]
+    fn test_tail_call_flag() {
+        let mut scanner = Scanner::new(
+            "return f(); func g() { return f(); return f() + 1; return (x) => x(); }".to_string(),
+        );
+        let mut parser = Parser::new(scanner.scan_tokens().unwrap());
+        let statements = parser.parse().unwrap();
+
+        let tail_call = |stmt: &Stmt| match stmt {
+            Stmt::Return(r) => r.tail_call,
+            _ => panic!("expect return statement"),
+        };
+        assert!(!tail_call(&statements[0]));
+        let Stmt::Function(g) = &statements[1] else {
+            panic!("expect function declaration");
+        };
+        assert!(tail_call(&g.body[0]));
+        assert!(!tail_call(&g.body[1]));
+    }
+
+    #[test]
+    fn test_stack_overflow() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_max_call_depth(50);
+        run(&interpreter, "func f(n) { return 1 + f(n + 1); }").unwrap();
+        match run(&interpreter, "\nf(0);") {
+            Err(LoxResult::RuntimeError {
+                message,
+                backtrace,
+                token,
+            }) => {
+                assert_eq!(message, "stack overflow");
+                assert_eq!(token.line, 1);
+                assert_eq!(backtrace.len(), 50);
+                assert_eq!(backtrace[0].to_string(), "at f (line 2)");
+                assert_eq!(backtrace[1].to_string(), "at f (line 1)");
+            }
+            other => panic!("expect stack overflow, got {:?}", other),
+        }
+        // 出错后调用栈恢复，可继续调用
+        run(&interpreter, "func g() { return 1; } var x = g();").unwrap();
+        assert_eq!(get(&interpreter, "x"), Object::Number(1.0));
+    }
+
+    #[test]
+    fn test_tail_call_backtrace() {
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "func g() { return 1 + nil; }
+             func h() { return g(); }
+             func f() { return h(); }",
+        )
+        .unwrap();
+        // 尾调用链共用一个栈帧，显示最后被尾调用的函数
+        match run(&interpreter, "\n\nf();") {
+            Err(LoxResult::RuntimeError { backtrace, .. }) => {
+                let frames: Vec<String> = backtrace.iter().map(|f| f.to_string()).collect();
+                assert_eq!(frames, vec!["at f (line 3)", "at g (line 2)"]);
+            }
+            other => panic!("expect runtime error, got {:?}", other),
+        }
+        assert!(interpreter.backtrace().is_empty());
+
+        // 尾调用循环不增加调用栈深度
+        run(
+            &interpreter,
+            "func count(n) { if (n == 0) return 0; return count(n - 1); } var c = count(10000);",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "c"), Object::Number(0.0));
+    }
+
+    #[test]
+    fn test_default_call_depth_fits_test_thread() {
+        // 测试线程只有 2MB 栈，默认深度下应报告可捕获的运行时错误而不是崩溃
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "func f(n) { try { { if (n == 0) return 0; var x = 1 + (1 + (1 + f(n - 1))); return x; } }
+                 catch (e) { throw e; } }
+             var m; try { f(100000); } catch (e) { m = e.message; }
+             func g(n) { if (n == 0) return 0; return 1 + g(n - 1); }
+             var d = g(DEPTH - 1);"
+                .replace("DEPTH", &DEFAULT_MAX_CALL_DEPTH.to_string())
+                .as_str(),
+        )
+        .unwrap();
+        assert_eq!(
+            get(&interpreter, "m"),
+            Object::Str("stack overflow".to_string())
+        );
+        assert_eq!(
+            get(&interpreter, "d"),
+            Object::Number((DEFAULT_MAX_CALL_DEPTH - 1) as f64)
+        );
+    }
+
+    #[test]
+    fn test_tail_call_in_try_stack_overflow() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_max_call_depth(50);
+        run(
+            &interpreter,
+            "func f(n) { try { return f(n + 1); } catch (e) { return e.message; } }
+             var m = f(0);
+             func g(n) { try { return g(n + 1); } catch (e) {} } var x = g(0);",
+        )
+        .unwrap();
+        assert_eq!(
+            get(&interpreter, "m"),
+            Object::Str("stack overflow".to_string())
+        );
+        assert_eq!(get(&interpreter, "x"), Object::Nil);
+
+        match run(
+            &interpreter,
+            "func h(n) { try { return h(n + 1); } finally {} } h(0);",
+        ) {
+            Err(LoxResult::RuntimeError {
+                message, backtrace, ..
+            }) => {
+                assert_eq!(message, "stack overflow");
+                assert_eq!(backtrace.len(), 50);
+            }
+            other => panic!("expect stack overflow, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_runtime_error_backtrace() {
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "func inner() { return nil + 1; }\nfunc outer() {\n var x = inner();\n return x; }",
+        )
+        .unwrap();
+        match run(&interpreter, "\n\n\n\nouter();") {
+            Err(LoxResult::RuntimeError {
+                token, backtrace, ..
+            }) => {
+                assert_eq!(token.line, 1);
+                let frames: Vec<String> = backtrace.iter().map(|f| f.to_string()).collect();
+                assert_eq!(frames, vec!["at outer (line 5)", "at inner (line 3)"]);
+            }
+            other => panic!("expect runtime error, got {:?}", other),
+        }
+        assert!(interpreter.backtrace().is_empty());
+
+        match run(&interpreter, "nil + 1;") {
+            Err(LoxResult::RuntimeError { backtrace, .. }) => assert!(backtrace.is_empty()),
+            other => panic!("expect runtime error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_throw_catch() {
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "var x; try { throw \"oops\"; x = 1; } catch (e) { x = e; }
+             func f(n) { if (n == 0) throw 42; return f(n - 1) + 1; }
+             var y; try { f(3); } catch (e) { y = e; }",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "x"), Object::Str("oops".to_string()));
+        assert_eq!(get(&interpreter, "y"), Object::Number(42.0));
+        assert!(interpreter.backtrace().is_empty());
+    }
+
+    #[test]
+    fn test_catch_runtime_error() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_max_call_depth(20);
+        run(
+            &interpreter,
+            "var m; var l; try {\n nil + 1; } catch (e) { m = e.message; l = e.line; }
+             func r() { return 1 + r(); }
+             var s; try { r(); } catch (e) { s = e.message; }
+             var u; try { undefined; } catch (e) { u = e.message; }",
+        )
+        .unwrap();
+        assert_eq!(
+            get(&interpreter, "m"),
+            Object::Str("interpreter error invalid number".to_string())
+        );
+        assert_eq!(get(&interpreter, "l"), Object::Number(2.0));
+        assert_eq!(
+            get(&interpreter, "s"),
+            Object::Str("stack overflow".to_string())
+        );
+        assert_eq!(
+            get(&interpreter, "u"),
+            Object::Str("undefined variable `undefined`".to_string())
+        );
+    }
+
+    #[test]
+    fn test_finally() {
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "var trace = 0;
+             func f() { try { return 1; } finally { trace += 1; } }
+             var a = f();
+             while (true) { try { break; } finally { trace += 10; } }
+             try { try { throw 1; } finally { trace += 100; } } catch (e) {}
+             func g() { try { return 1; } finally { return 2; } }
+             var b = g();
+             func h() { throw 5; }
+             func k() { try { return h(); } catch (e) { return e + 1; } finally { trace += 1000; } }
+             var c = k();",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "a"), Object::Number(1.0));
+        assert_eq!(get(&interpreter, "b"), Object::Number(2.0));
+        assert_eq!(get(&interpreter, "c"), Object::Number(6.0));
+        assert_eq!(get(&interpreter, "trace"), Object::Number(1111.0));
+    }
+
+    #[test]
+    fn test_uncaught_throw() {
+        let interpreter = Interpreter::new();
+        assert!(matches!(
+            run(&interpreter, "try { throw 1; } finally {}"),
+            Err(LoxResult::Throw {
+                value: Object::Number(_),
+                ..
+            })
+        ));
+        assert!(matches!(
+            run(&interpreter, "try {}"),
+            Err(LoxResult::ParseError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_step_limit() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_step_limit(Some(1000));
+        interpreter.reset_budget();
+        let result = run(
+            &interpreter,
+            "var n = 0; try { while (true) { n += 1; } } catch (e) { n = -1; } finally { n = -2; }",
+        );
+        assert!(matches!(result, Err(LoxResult::Interrupted { .. })));
+        assert!(matches!(get(&interpreter, "n"), Object::Number(n) if n > 0.0));
+
+        interpreter.reset_budget();
+        run(&interpreter, "var m = 1;").unwrap();
+        assert_eq!(get(&interpreter, "m"), Object::Number(1.0));
+    }
+
+    #[test]
+    fn test_step_limit_reentrant_call() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_step_limit(Some(1000));
+        // 本地函数回调脚本函数时不能重置预算
+        let reenter = NativeFunction::new("reenter", 0, |interpreter, _| {
+            interpreter.call_function("spin", Vec::new())
+        });
+        interpreter.globals.borrow_mut().define(
+            "reenter".to_string(),
+            Object::Func(LoxCallable {
+                func: Rc::new(reenter),
+            }),
+        );
+        interpreter.reset_budget();
+        let result = run(
+            &interpreter,
+            "func spin() { var n = 0; while (n < 5) n += 1; }
+             for (var i = 0; i < 500; i++) reenter();",
+        );
+        assert!(matches!(result, Err(LoxResult::Interrupted { .. })));
+    }
+
+    #[test]
+    fn test_time_limit() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_time_limit(Some(std::time::Duration::from_millis(50)));
+        interpreter.reset_budget();
+        let result = run(&interpreter, "func f() { while (true) {} } f();");
+        match result {
+            Err(LoxResult::Interrupted { message }) => assert_eq!(message, "time limit exceeded"),
+            other => panic!("expect interrupted, got {:?}", other),
+        }
+        assert!(interpreter.backtrace().is_empty());
+    }
+
+    #[test]
+    fn test_cancel_flag() {
+        let interpreter = Interpreter::new();
+        let flag = interpreter.cancel_flag();
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            flag.store(true, std::sync::atomic::Ordering::Relaxed);
+        });
+        let result = run(&interpreter, "while (true) {}");
+        handle.join().unwrap();
+        assert!(matches!(result, Err(LoxResult::Interrupted { .. })));
+
+        // 取消标记保持有效，清除后才能再次运行
+        interpreter.reset_budget();
+        assert!(matches!(
+            run(&interpreter, "var x = 1;"),
+            Err(LoxResult::Interrupted { .. })
+        ));
+        interpreter.clear_cancel();
+        interpreter.reset_budget();
+        run(&interpreter, "var x = 1;").unwrap();
+        assert_eq!(get(&interpreter, "x"), Object::Number(1.0));
+    }
+
+    #[test]
+    fn test_string_concat() {
+        let interpreter = Interpreter::new();
+        run(&interpreter, "var s = \"foo\" + \"bar\"; s += \"!\";").unwrap();
+        assert_eq!(get(&interpreter, "s"), Object::Str("foobar!".to_string()));
+        assert_eq!(interpreter.allocated(), 13);
+    }
+
+    #[test]
+    fn test_memory_limit() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_memory_limit(Some(1024));
+        interpreter.reset_budget();
+        let result = run(
+            &interpreter,
+            "var s = \"x\"; var caught = false; try { while (true) { s += s; } } catch (e) { caught = e.message; }",
+        );
+        assert!(result.is_ok());
+        assert_eq!(
+            get(&interpreter, "caught"),
+            Object::Str("memory limit of 1024 bytes exceeded".to_string())
+        );
+        assert!(interpreter.allocated() <= 1024);
+
+        let result = run(&interpreter, "func f(...xs) {} f(1, 2, 3);");
+        assert!(matches!(result, Err(LoxResult::RuntimeError { .. })));
+    }
+
+    #[test]
+    fn test_native_memory_limit() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_memory_limit(Some(4096));
+        interpreter.reset_budget();
+        // 结果的长度超出预算时在分配之前报错
+        let result = run(
+            &interpreter,
+            "var s = \"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\";
+             s = replace(s, \"a\", s);",
+        );
+        assert!(matches!(
+            result,
+            Err(LoxResult::RuntimeError { message, .. })
+                if message == "replace: memory limit of 4096 bytes exceeded"
+        ));
+        assert!(interpreter.allocated() <= 4096);
+
+        // 共享元素的嵌套列表打印形式呈指数增长，写入过程中即停止
+        interpreter.reset_budget();
+        let result = run(
+            &interpreter,
+            "var l = list(\"xxxxxxxxxx\"); for (var i = 0; i < 40; i++) l = list(l, l); to_string(l);",
+        );
+        assert!(matches!(
+            result,
+            Err(LoxResult::RuntimeError { message, .. })
+                if message == "to_string: memory limit of 4096 bytes exceeded"
+        ));
+        assert!(interpreter.allocated() <= 4096);
+
+        // 预留的上限多于实际长度时归还
+        interpreter.reset_budget();
+        run(&interpreter, "var u = upper(\"abc\");").unwrap();
+        assert_eq!(interpreter.allocated(), 3);
+
+        // 复制字符串同样计入预算
+        interpreter.reset_budget();
+        run(&interpreter, "var t = str(u);").unwrap();
+        assert_eq!(interpreter.allocated(), 3);
+
+        // 宿主函数的结果在转换之前检查预算
+        let repeat = NativeFunction::from_fn("repeat", |n: i64| vec!["x"; n as usize]);
+        interpreter.globals.borrow_mut().define(
+            "repeat".to_string(),
+            Object::Func(LoxCallable {
+                func: Rc::new(repeat),
+            }),
+        );
+        interpreter.reset_budget();
+        assert!(matches!(
+            run(&interpreter, "repeat(1000);"),
+            Err(LoxResult::RuntimeError { message, .. })
+                if message == "repeat: memory limit of 4096 bytes exceeded"
+        ));
+        assert_eq!(interpreter.allocated(), 0);
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let interpreter = Interpreter::with_capabilities(Capabilities::none());
+        let result = run(&interpreter, "clock();");
+        assert!(matches!(result, Err(LoxResult::RuntimeError { .. })));
+
+        run(
+            &interpreter,
+            "var msg; try { clock(); } catch (e) { msg = e.message; }",
+        )
+        .unwrap();
+        assert_eq!(
+            get(&interpreter, "msg"),
+            Object::Str("<native fn clock> requires capability `clock`".to_string())
+        );
+
+        let interpreter =
+            Interpreter::with_capabilities(Capabilities::none().with(Capability::Clock));
+        run(&interpreter, "var t = clock();").unwrap();
+        assert!(matches!(get(&interpreter, "t"), Object::Number(_)));
+    }
+
+    #[test]
+    fn test_capabilities_indirect_call() {
+        let interpreter = Interpreter::with_capabilities(Capabilities::none());
+        // 绑定方法转发被包装方法所需的能力
+        let gated =
+            NativeFunction::new("gated", 0, |_, _| Ok(Object::Nil)).requires(Capability::FileRead);
+        let bound = BoundMethod::new(
+            Object::Nil,
+            LoxCallable {
+                func: Rc::new(gated),
+            },
+        );
+        // 本地函数直接调用其他函数，不经过调用表达式
+        let apply = NativeFunction::new("apply", 1, |interpreter, arguments| match &arguments[0] {
+            Object::Func(f) => f.call(interpreter, Vec::new()),
+            _ => Ok(Object::Nil),
+        });
+        for (name, f) in [
+            ("bound", Rc::new(bound) as Rc<dyn Callable>),
+            ("apply", Rc::new(apply)),
+        ] {
+            interpreter
+                .globals
+                .borrow_mut()
+                .define(name.to_string(), Object::Func(LoxCallable { func: f }));
+        }
+
+        assert!(matches!(
+            run(&interpreter, "bound();"),
+            Err(LoxResult::RuntimeError { message, .. })
+                if message == "<native fn gated> requires capability `file_read`"
+        ));
+        assert!(matches!(
+            run(&interpreter, "apply(clock);"),
+            Err(LoxResult::RuntimeError { message, .. })
+                if message == "apply: <native fn clock> requires capability `clock`"
+        ));
+        assert!(matches!(
+            run(&interpreter, "apply(read_file);"),
+            Err(LoxResult::RuntimeError { message, .. })
+                if message == "apply: <native fn read_file> requires capability `file_read`"
+        ));
+    }
+
+    #[test]
+    fn test_foreign_methods() {
+        struct Counter {
+            count: std::cell::Cell<f64>,
+        }
+
+        let mut interpreter = Interpreter::new();
+        interpreter.register_method("Counter", "add", 1, |_, counter: &Counter, args| {
+            if let Object::Number(n) = args[0] {
+                counter.count.set(counter.count.get() + n);
+            }
+            Ok(Object::Number(counter.count.get()))
+        });
+        let counter = Foreign::new(
+            "Counter",
+            Counter {
+                count: std::cell::Cell::new(0.0),
+            },
+        );
+        interpreter
+            .globals
+            .borrow_mut()
+            .define("c".to_string(), Object::Foreign(counter.clone()));
+
+        run(
+            &interpreter,
+            "c.add(2); var add = c.add; var n = add(3); var s = to_string(c); var t = type(c);",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "n"), Object::Number(5.0));
+        assert_eq!(
+            get(&interpreter, "s"),
+            Object::Str("<foreign Counter>".to_string())
+        );
+        assert_eq!(get(&interpreter, "t"), Object::Str("foreign".to_string()));
+        assert_eq!(counter.downcast_ref::<Counter>().unwrap().count.get(), 5.0);
+        assert_eq!(get(&interpreter, "c"), Object::Foreign(counter));
+
+        assert!(matches!(
+            run(&interpreter, "c.remove(1);"),
+            Err(LoxResult::RuntimeError { message, .. }) if message == "undefined property `remove`"
+        ));
+        assert!(matches!(
+            run(&interpreter, "c.add();"),
+            Err(LoxResult::RuntimeError { message, .. }) if message == "expect 1 arguments but got 0"
+        ));
+    }
+
+    #[test]
+    fn test_call_function() {
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "func add(a, b = 10) { return a + b; } func fail() { throw \"bad\"; } var x = 1;",
+        )
+        .unwrap();
+
+        assert_eq!(
+            interpreter
+                .call_function("add", vec![Object::Number(1.0), Object::Number(2.0)])
+                .unwrap(),
+            Object::Number(3.0)
+        );
+        assert_eq!(
+            interpreter
+                .call_function("add", vec![Object::Number(1.0)])
+                .unwrap(),
+            Object::Number(11.0)
+        );
+        assert_eq!(
+            interpreter
+                .call_function("len", vec![Object::Str("abc".to_string())])
+                .unwrap(),
+            Object::Number(3.0)
+        );
+
+        let message = |result: Result<Object, LoxResult>| match result {
+            Err(LoxResult::RuntimeError { message, .. }) => message,
+            other => panic!("expect runtime error, got {:?}", other),
+        };
+        assert_eq!(
+            message(interpreter.call_function("missing", vec![])),
+            "undefined function `missing`"
+        );
+        assert_eq!(
+            message(interpreter.call_function("x", vec![])),
+            "`x` is a number, not a function"
+        );
+        assert_eq!(
+            message(interpreter.call_function("add", vec![])),
+            "expect 1 to 2 arguments but got 0"
+        );
+        assert!(matches!(
+            interpreter.call_function("fail", vec![]),
+            Err(LoxResult::Throw { value: Object::Str(s), .. }) if s == "bad"
+        ));
+        assert!(interpreter.backtrace().is_empty());
+    }
+
+    #[test]
+    fn test_constants_at_runtime() {
+        // REPL 中每行单独解析，之前定义的全局常量由运行时检查
+        let interpreter = Interpreter::new();
+        run(&interpreter, "const a = 1;").unwrap();
+        let message = |source: &str| match run(&interpreter, source) {
+            Err(LoxResult::RuntimeError { message, .. }) => message,
+            other => panic!("expect runtime error, got {:?}", other),
+        };
+        assert_eq!(message("a = 2;"), "cannot assign to constant `a`");
+        assert_eq!(message("a++;"), "cannot assign to constant `a`");
+        assert_eq!(
+            message("func f() { a += 1; } f();"),
+            "cannot assign to constant `a`"
+        );
+        assert_eq!(message("var a = 2;"), "cannot redeclare constant `a`");
+        assert_eq!(get(&interpreter, "a"), Object::Number(1.0));
+
+        // 本地函数及内置常量不能被赋值
+        assert_eq!(message("clock = nil;"), "cannot assign to constant `clock`");
+        assert_eq!(message("PI = 3;"), "cannot assign to constant `PI`");
+
+        // 内层作用域可以遮蔽常量
+        run(&interpreter, "var b; { var a = 2; a = 3; b = a; }").unwrap();
+        assert_eq!(get(&interpreter, "b"), Object::Number(3.0));
+
+        // 顶层不能重新声明本地函数，内层作用域可以遮蔽
+        assert_eq!(
+            message("var clock = nil;"),
+            "cannot redeclare builtin `clock`"
+        );
+        assert_eq!(message("func len(s) {}"), "cannot redeclare builtin `len`");
+        assert_eq!(message("const PI = 3;"), "cannot redeclare builtin `PI`");
+        run(
+            &interpreter,
+            "var l; { var len = 1; l = len; } var n = len(\"ab\");",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "l"), Object::Number(1.0));
+        assert_eq!(get(&interpreter, "n"), Object::Number(2.0));
+    }
+
+    #[test]
+    fn test_for_fresh_binding_per_iteration() {
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "var f0; var f1; var f2;
+            for (var i = 0; i < 3; i = i + 1) {
+                var f = func() { return i; };
+                if (i == 0) f0 = f; if (i == 1) f1 = f; if (i == 2) f2 = f;
+            }
+            var a = f0(); var b = f1(); var c = f2();",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "a"), Object::Number(0.0));
+        assert_eq!(get(&interpreter, "b"), Object::Number(1.0));
+        assert_eq!(get(&interpreter, "c"), Object::Number(2.0));
+
+        // 同一次迭代中闭包能看到之后的修改，循环变量不泄漏到外层
+        run(
+            &interpreter,
+            "var g;
+            for (var i = 0; i < 2; i = i + 1) { if (i == 0) g = () => i; i = i + 10; }
+            var a = g();",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "a"), Object::Number(10.0));
+        assert!(run(&interpreter, "var leaked = i;").is_err());
+
+        run(
+            &interpreter,
+            "var sum = 0; var j = 0;
+            for (; j < 10; j = j + 1) { if (j == 4) break; sum = sum + j; }",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "sum"), Object::Number(6.0));
+        assert_eq!(get(&interpreter, "j"), Object::Number(4.0));
+    }
+
+    #[test]
+    fn test_list_methods() {
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "var l = list(1, 2); var n = l.push(3); var last = l.pop(); var first = l.get(0);",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "n"), Object::Number(3.0));
+        assert_eq!(get(&interpreter, "last"), Object::Number(3.0));
+        assert_eq!(get(&interpreter, "first"), Object::Number(1.0));
+        assert!(run(&interpreter, "l.get(5);").is_err());
+        assert!(run(&interpreter, "l.missing();").is_err());
+
+        // 绑定方法按名称传参时不包括接收者
+        run(
+            &interpreter,
+            "var m = l.push(value: 4); var second = l.get(index: 1);",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "m"), Object::Number(3.0));
+        assert_eq!(get(&interpreter, "second"), Object::Number(2.0));
+        assert!(matches!(
+            run(&interpreter, "l.get(self: 0);"),
+            Err(LoxResult::RuntimeError { message, .. }) if message == "unknown argument `self`"
+        ));
+        // 参数位置不计入接收者
+        assert!(matches!(
+            run(&interpreter, "l.get(\"a\");"),
+            Err(LoxResult::RuntimeError { message, .. })
+                if message == "get: expect non-negative integer as argument 1 but got string"
+        ));
+
+        // 包含自身的列表
+        run(
+            &interpreter,
+            "var c = list(1); c.push(c); var s = to_string(c);",
+        )
+        .unwrap();
+        assert_eq!(
+            get(&interpreter, "s"),
+            Object::Str("[1, [...]]".to_string())
+        );
+    }
+
+    #[test]
+    fn test_for_closures_in_list() {
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "var fns = list();
+            for (var i = 0; i < 3; i = i + 1) fns.push(func() { return i; });
+            var a = fns.get(0)(); var b = fns.get(1)(); var c = fns.get(2)();",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "a"), Object::Number(0.0));
+        assert_eq!(get(&interpreter, "b"), Object::Number(1.0));
+        assert_eq!(get(&interpreter, "c"), Object::Number(2.0));
+    }
+
+    #[test]
+    fn test_list_methods_separate_from_foreign() {
+        // 宿主类型名为 list 的 Foreign 值只使用宿主注册的方法
+        let mut interpreter = Interpreter::new();
+        interpreter.register_method("list", "size", 0, |_, items: &Vec<f64>, _| {
+            Ok(Object::Number(items.len() as f64))
+        });
+        interpreter.globals.borrow_mut().define(
+            "host".to_string(),
+            Object::Foreign(Foreign::new("list", vec![1.0, 2.0])),
+        );
+        run(
+            &interpreter,
+            "var n = host.size(); var m = list(1).push(2);",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "n"), Object::Number(2.0));
+        assert_eq!(get(&interpreter, "m"), Object::Number(2.0));
+        assert!(matches!(
+            run(&interpreter, "host.push(1);"),
+            Err(LoxResult::RuntimeError { message, .. }) if message == "undefined property `push`"
+        ));
+        assert!(matches!(
+            run(&interpreter, "list().size();"),
+            Err(LoxResult::RuntimeError { message, .. }) if message == "undefined property `size`"
+        ));
+    }
+}
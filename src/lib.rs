@@ -13,3 +13,6 @@ pub mod stmt;
 
 pub mod native;
 pub mod resolver;
+
+#[cfg(test)]
+mod test_util;
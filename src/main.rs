@@ -6,8 +6,22 @@ use std::str;
 use lox_ast::error::LoxResult;
 use lox_ast::interpreter::Interpreter;
 use lox_ast::parser::Parser;
+use lox_ast::resolver::Resolver;
 use lox_ast::scanner::Scanner;
+// 解释器递归执行，为调用深度上限预留足够的栈空间
+const STACK_SIZE: usize = 64 * 1024 * 1024;
+// 在 STACK_SIZE 的栈上可以安全使用的调用深度
+const MAX_CALL_DEPTH: usize = 1000;
+
 fn main() {
+    let child = std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(run_main)
+        .expect("cannot spawn interpreter thread");
+    child.join().expect("interpreter thread panicked");
+}
+
+fn run_main() {
     println!("Hello, Lox!");
     let lox = Lox::new();
 
@@ -26,19 +40,21 @@ struct Lox {
 }
 impl Lox {
     fn new() -> Self {
-        Self {
-            interpreter: Interpreter::new(),
-        }
+        let mut interpreter = Interpreter::new();
+        interpreter.set_max_call_depth(MAX_CALL_DEPTH);
+        Self { interpreter }
     }
     fn run_file(&self, path: &str) -> io::Result<()> {
         let mut f = File::open(path).expect("cannot open file");
         let mut buf = String::new();
 
         f.read_to_string(&mut buf)?;
+        self.interpreter.set_script_path(path);
         match self.run(buf) {
-            Ok(_) => (),
+            Ok(Some(code)) => std::process::exit(code),
+            Ok(None) => (),
             Err(e) => {
-                eprintln!("{:?}", e);
+                e.report_error();
                 std::process::exit(64);
             }
         }
@@ -58,9 +74,10 @@ impl Lox {
                     break;
                 }
                 match self.run(line_content) {
-                    Ok(_) => (),
+                    Ok(Some(code)) => std::process::exit(code),
+                    Ok(None) => (),
                     Err(e) => {
-                        eprintln!("{:?}", e);
+                        e.report_error();
                         // std::process::exit(64);
                     }
                 }
@@ -71,10 +88,11 @@ impl Lox {
         }
     }
 
-    fn run(&self, source: String) -> Result<(), LoxResult> {
+    // 脚本调用 exit 时返回退出码
+    fn run(&self, source: String) -> Result<Option<i32>, LoxResult> {
         if source == "@" {
             self.interpreter.print_environment();
-            return Ok(());
+            return Ok(None);
         }
         let mut scanner = Scanner::new(source);
         let tokens = scanner.scan_tokens()?;
@@ -85,8 +103,11 @@ impl Lox {
 
         let mut parser = Parser::new(tokens);
         // let interpreter = Interpreter::new();  // 解释器 应只需一个，否则命令行执行时每次都会初始化
-        self.interpreter.interpreter(&parser.parse()?);
-
-        Ok(())
+        let statements = parser.parse()?;
+        let resolver = Resolver::default();
+        for stmt in &statements {
+            resolver.resolve_stmt(stmt)?;
+        }
+        Ok(self.interpreter.interpreter(&statements))
     }
 }
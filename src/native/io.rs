@@ -0,0 +1,179 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, Read, Write};
+use std::path::Path;
+
+use crate::core::{Capability, Object};
+use crate::error::LoxResult;
+use crate::interpreter::Interpreter;
+
+use super::{build_str, expect_str, reserve_list, NativeFunction};
+
+// 文件及标准输入相关的本地函数，需要解释器授予相应能力
+pub fn natives() -> Vec<NativeFunction> {
+    vec![
+        NativeFunction::new("read_file", 1, read_file).requires(Capability::FileRead),
+        NativeFunction::new("write_file", 2, write_file)
+            .params(&["path", "content"])
+            .requires(Capability::FileWrite),
+        NativeFunction::new("append_file", 2, append_file)
+            .params(&["path", "content"])
+            .requires(Capability::FileWrite),
+        NativeFunction::new("read_line", 0, read_line).requires(Capability::Stdin),
+        NativeFunction::new("exists", 1, exists).requires(Capability::FileRead),
+        NativeFunction::new("list_dir", 1, list_dir).requires(Capability::FileRead),
+    ]
+}
+
+fn io_error(action: &str, path: &str, e: io::Error) -> LoxResult {
+    LoxResult::native_error(format!("cannot {} `{}`: {}", action, path, e))
+}
+
+fn read_file(interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let path = expect_str(&arguments, 0)?;
+    let file = File::open(path).map_err(|e| io_error("read", path, e))?;
+    let size = file
+        .metadata()
+        .map_err(|e| io_error("read", path, e))?
+        .len();
+
+    // 按读取前的文件大小预留内存，最多读取这么多字节
+    build_str(interpreter, size as usize, || {
+        let mut content = String::new();
+        file.take(size)
+            .read_to_string(&mut content)
+            .map_err(|e| io_error("read", path, e))?;
+        Ok(content)
+    })
+}
+
+fn write_file(_interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let path = expect_str(&arguments, 0)?;
+    let content = expect_str(&arguments, 1)?;
+    fs::write(path, content).map_err(|e| io_error("write", path, e))?;
+
+    Ok(Object::Nil)
+}
+
+fn append_file(_interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let path = expect_str(&arguments, 0)?;
+    let content = expect_str(&arguments, 1)?;
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut f| f.write_all(content.as_bytes()))
+        .map_err(|e| io_error("append to", path, e))?;
+
+    Ok(Object::Nil)
+}
+
+// 读取一行，不含换行符；读到末尾时返回 nil
+fn read_line(interpreter: &Interpreter, _arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    // 最多读取剩余内存预算加上换行符的字节数
+    let available = interpreter.available_memory().unwrap_or(usize::MAX);
+    let limit = (available as u64).saturating_add(2);
+    let mut line = String::new();
+    let count = io::stdin()
+        .lock()
+        .take(limit)
+        .read_line(&mut line)
+        .map_err(|e| io_error("read", "stdin", e))?;
+    if count == 0 {
+        return Ok(Object::Nil);
+    }
+
+    let len = line.trim_end_matches(['\n', '\r']).len();
+    line.truncate(len);
+    build_str(interpreter, len, || Ok(line))
+}
+
+fn exists(_interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    Ok(Object::Bool(Path::new(expect_str(&arguments, 0)?).exists()))
+}
+
+// 目录下的文件名，按名称排序
+fn list_dir(interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let path = expect_str(&arguments, 0)?;
+    let entries = fs::read_dir(path).map_err(|e| io_error("list", path, e))?;
+    // 逐个计入内存预算
+    let mut names = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|e| io_error("list", path, e))?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        reserve_list(interpreter, 1)?;
+        interpreter
+            .reserve(name.len())
+            .map_err(LoxResult::native_error)?;
+        names.push(name);
+    }
+    names.sort();
+
+    Ok(Object::new_list(
+        names.into_iter().map(Object::Str).collect(),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::core::{Capabilities, Capability, Object};
+    use crate::error::LoxResult;
+    use crate::interpreter::Interpreter;
+    use crate::test_util::{get, run};
+
+    #[test]
+    fn test_file_io() {
+        let dir = std::env::temp_dir().join(format!("lox-io-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("a.txt");
+        let path = path.to_str().unwrap();
+
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            &format!(
+                "var p = \"{path}\"; var before = exists(p);
+                 write_file(p, \"hello\"); append_file(p, \" world\");
+                 var content = read_file(p); var after = exists(p);
+                 var names = list_dir(\"{}\");",
+                dir.to_str().unwrap()
+            ),
+        )
+        .unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(get(&interpreter, "before"), Object::Bool(false));
+        assert_eq!(get(&interpreter, "after"), Object::Bool(true));
+        assert_eq!(
+            get(&interpreter, "content"),
+            Object::Str("hello world".to_string())
+        );
+        assert_eq!(
+            get(&interpreter, "names"),
+            Object::new_list(vec![Object::Str("a.txt".to_string())])
+        );
+
+        let result = run(&interpreter, &format!("read_file(\"{path}\");"));
+        assert!(matches!(
+            result,
+            Err(LoxResult::RuntimeError { message, .. }) if message.starts_with("read_file: cannot read")
+        ));
+    }
+
+    #[test]
+    fn test_io_capabilities() {
+        let interpreter =
+            Interpreter::with_capabilities(Capabilities::all().without(Capability::FileWrite));
+        assert!(run(&interpreter, "exists(\".\");").is_ok());
+        assert!(matches!(
+            run(&interpreter, "write_file(\"x\", \"y\");"),
+            Err(LoxResult::RuntimeError { message, .. })
+                if message == "<native fn write_file> requires capability `file_write`"
+        ));
+
+        let interpreter = Interpreter::with_capabilities(Capabilities::none());
+        assert!(run(&interpreter, "read_file(\"Cargo.toml\");").is_err());
+        assert!(run(&interpreter, "read_line();").is_err());
+    }
+}
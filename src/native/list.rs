@@ -0,0 +1,72 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::core::Object;
+use crate::error::LoxResult;
+use crate::interpreter::Interpreter;
+
+use super::{expect_index, reserve_list, NativeFunction};
+
+// 创建列表的本地函数
+pub fn natives() -> Vec<NativeFunction> {
+    vec![NativeFunction::new("list", 0, list).variadic()]
+}
+
+// 列表的方法，第一个实参为列表本身，形参名也包括接收者
+pub fn methods() -> Vec<NativeFunction> {
+    vec![
+        NativeFunction::new("push", 1, push).params(&["self", "value"]),
+        NativeFunction::new("pop", 0, pop),
+        NativeFunction::new("get", 1, get).params(&["self", "index"]),
+    ]
+}
+
+fn receiver(arguments: &[Object]) -> Result<&Rc<RefCell<Vec<Object>>>, LoxResult> {
+    match &arguments[0] {
+        Object::List(items) => Ok(items),
+        other => Err(LoxResult::native_error(format!(
+            "invalid receiver {}",
+            other.type_name()
+        ))),
+    }
+}
+
+// 接收者之后的实参，类型错误报告的参数位置不计入接收者
+fn method_arguments(arguments: &[Object]) -> &[Object] {
+    &arguments[1..]
+}
+
+fn list(interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    reserve_list(interpreter, arguments.len())?;
+    Ok(Object::new_list(arguments))
+}
+
+// 追加到末尾，返回新的长度
+fn push(interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let items = receiver(&arguments)?;
+    reserve_list(interpreter, 1)?;
+    items.borrow_mut().push(arguments[1].clone());
+
+    Ok(Object::Number(items.borrow().len() as f64))
+}
+
+// 移除并返回最后一个元素，空列表返回 nil
+fn pop(_interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let items = receiver(&arguments)?;
+    let last = items.borrow_mut().pop();
+
+    Ok(last.unwrap_or(Object::Nil))
+}
+
+fn get(_interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let items = receiver(&arguments)?;
+    let index = expect_index(method_arguments(&arguments), 0)?;
+    let items = items.borrow();
+    items.get(index).cloned().ok_or_else(|| {
+        LoxResult::native_error(format!(
+            "index {} out of bounds for list of length {}",
+            index,
+            items.len()
+        ))
+    })
+}
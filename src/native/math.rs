@@ -0,0 +1,182 @@
+use std::cell::Cell;
+use std::f64::consts;
+use std::time::SystemTime;
+
+use crate::core::Object;
+use crate::error::LoxResult;
+use crate::interpreter::Interpreter;
+
+use super::{argument, NativeFunction};
+
+// 数学相关的本地函数
+pub fn natives() -> Vec<NativeFunction> {
+    vec![
+        NativeFunction::from_fn("sqrt", f64::sqrt),
+        NativeFunction::from_fn("pow", f64::powf).params(&["base", "exponent"]),
+        NativeFunction::from_fn("abs", f64::abs),
+        NativeFunction::from_fn("floor", f64::floor),
+        NativeFunction::from_fn("ceil", f64::ceil),
+        NativeFunction::from_fn("round", f64::round),
+        NativeFunction::from_fn("min", f64::min),
+        NativeFunction::from_fn("max", f64::max),
+        NativeFunction::from_fn("sin", f64::sin),
+        NativeFunction::from_fn("cos", f64::cos),
+        NativeFunction::from_fn("tan", f64::tan),
+        NativeFunction::from_fn("log", f64::ln),
+        NativeFunction::from_fn("exp", f64::exp),
+        NativeFunction::new("random", 0, random),
+        NativeFunction::new("random_int", 2, random_int).params(&["low", "high"]),
+    ]
+}
+
+// 数学常量
+pub fn constants() -> Vec<(&'static str, Object)> {
+    vec![
+        ("PI", Object::Number(consts::PI)),
+        ("E", Object::Number(consts::E)),
+    ]
+}
+
+// [0, 1) 之间的随机数
+fn random(interpreter: &Interpreter, _arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    Ok(Object::Number(interpreter.random().next_f64()))
+}
+
+// [a, b] 之间的随机整数
+fn random_int(interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let low: i64 = argument(&arguments, 0)?;
+    let high: i64 = argument(&arguments, 1)?;
+    if low > high {
+        return Err(LoxResult::native_error(format!(
+            "invalid range {} to {}",
+            low, high
+        )));
+    }
+
+    let span = high.abs_diff(low).wrapping_add(1);
+    let offset = match span {
+        0 => interpreter.random().next_u64(),
+        span => interpreter.random().next_u64() % span,
+    };
+    Ok(Object::Number(low.wrapping_add(offset as i64) as f64))
+}
+
+// 可设置种子的伪随机数生成器（splitmix64），相同种子产生相同序列
+pub struct Random {
+    state: Cell<u64>,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: Cell::new(seed),
+        }
+    }
+
+    // 以当前时间为种子
+    pub fn from_time() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default();
+        Self::new(seed)
+    }
+
+    pub fn seed(&self, seed: u64) {
+        self.state.set(seed);
+    }
+
+    pub fn next_u64(&self) -> u64 {
+        let state = self.state.get().wrapping_add(0x9E37_79B9_7F4A_7C15);
+        self.state.set(state);
+
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    pub fn next_f64(&self) -> f64 {
+        // 取高 53 位作为尾数
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::core::Object;
+    use crate::error::LoxResult;
+    use crate::interpreter::Interpreter;
+    use crate::test_util::{get, run};
+
+    #[test]
+    fn test_math_functions() {
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "var a = sqrt(16) + pow(2, 3) + abs(-1); var b = floor(1.5) + ceil(1.5) + round(2.5);
+             var c = min(1, 2) + max(1, 2); var d = cos(0) + exp(0) + log(E) + sin(0) + tan(0);
+             var p = PI;",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "a"), Object::Number(13.0));
+        assert_eq!(get(&interpreter, "b"), Object::Number(6.0));
+        assert_eq!(get(&interpreter, "c"), Object::Number(3.0));
+        assert_eq!(get(&interpreter, "d"), Object::Number(3.0));
+        assert_eq!(get(&interpreter, "p"), Object::Number(std::f64::consts::PI));
+
+        assert!(matches!(
+            run(&interpreter, "sqrt(\"4\");"),
+            Err(LoxResult::RuntimeError { message, .. })
+                if message == "sqrt: expect number as argument 1 but got string"
+        ));
+    }
+
+    #[test]
+    fn test_seeded_random() {
+        let source = "var xs = \"\"; var i = 0;
+            while (i < 20) { var r = random_int(1, 6); if (r < 1 or r > 6) xs = nil; xs += str(r); i++; }
+            var f = random();";
+        let mut first = Interpreter::new();
+        first.set_random_seed(42);
+        run(&first, source).unwrap();
+        let mut second = Interpreter::new();
+        second.set_random_seed(42);
+        run(&second, source).unwrap();
+
+        assert_eq!(get(&first, "xs"), get(&second, "xs"));
+        assert_eq!(get(&first, "f"), get(&second, "f"));
+        assert!(matches!(get(&first, "f"), Object::Number(f) if (0.0..1.0).contains(&f)));
+        assert!(run(&first, "random_int(3, 1);").is_err());
+        assert!(matches!(
+            run(&first, "random_int(1.5, 2);"),
+            Err(LoxResult::RuntimeError { message, .. })
+                if message == "random_int: expect integer as argument 1 but got number"
+        ));
+    }
+
+    #[test]
+    fn test_random_int_large_span() {
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "var a = random_int(-9000000000000000000, 9000000000000000000); var b = random_int(-9223372036854775808, 9223372036854774784);
+             var c = random_int(5, 5);",
+        )
+        .unwrap();
+        assert!(matches!(get(&interpreter, "a"), Object::Number(n) if (-9e18..=9e18).contains(&n)));
+        assert!(matches!(get(&interpreter, "b"), Object::Number(_)));
+        assert_eq!(get(&interpreter, "c"), Object::Number(5.0));
+        // 超出整数范围的边界报错而不是溢出，2^63 恰好超出
+        assert!(matches!(
+            run(&interpreter, "random_int(0, 9223372036854775808);"),
+            Err(LoxResult::RuntimeError { message, .. })
+                if message == "random_int: expect integer as argument 2 but got number"
+        ));
+        assert!(matches!(
+            run(&interpreter, "random_int(0, 100000000000000000000);"),
+            Err(LoxResult::RuntimeError { message, .. })
+                if message == "random_int: expect integer as argument 2 but got number"
+        ));
+    }
+}
@@ -1,9 +1,273 @@
+use std::fmt::{self, Write};
+use std::mem;
 use std::time::SystemTime;
 
 use crate::core::*;
 use crate::error::*;
 use crate::interpreter::Interpreter;
 
+pub mod io;
+pub mod list;
+pub mod math;
+pub mod string;
+pub mod system;
+pub mod types;
+
+type NativeFn = Box<dyn Fn(&Interpreter, Vec<Object>) -> Result<Object, LoxResult>>;
+
+// 由 Rust 函数实现的本地函数
+pub struct NativeFunction {
+    name: &'static str,
+    arity: usize,
+    max_arity: Option<usize>,
+    // 形参名，声明后可以使用命名参数调用
+    params: &'static [&'static str],
+    capabilities: Vec<Capability>,
+    func: NativeFn,
+}
+
+impl NativeFunction {
+    pub fn new<F>(name: &'static str, arity: usize, func: F) -> Self
+    where
+        F: Fn(&Interpreter, Vec<Object>) -> Result<Object, LoxResult> + 'static,
+    {
+        Self {
+            name,
+            arity,
+            max_arity: Some(arity),
+            params: &[],
+            capabilities: Vec::new(),
+            func: Box::new(func),
+        }
+    }
+
+    // 由普通的 Rust 函数生成本地函数，参数个数及类型检查由参数类型决定
+    pub fn from_fn<Args, F>(name: &'static str, func: F) -> Self
+    where
+        F: IntoNative<Args> + 'static,
+    {
+        Self::new(name, F::arity(), move |interpreter, arguments| {
+            func.invoke(interpreter, &arguments)
+        })
+    }
+
+    // 可选参数的个数
+    pub fn optional(mut self, count: usize) -> Self {
+        self.max_arity = Some(self.arity + count);
+        self
+    }
+
+    // 不限参数个数
+    pub fn variadic(mut self) -> Self {
+        self.max_arity = None;
+        self
+    }
+
+    // 声明形参名，包括可选参数
+    pub fn params(mut self, names: &'static [&'static str]) -> Self {
+        self.params = names;
+        self
+    }
+
+    pub fn requires(mut self, capability: Capability) -> Self {
+        self.capabilities.push(capability);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        self.name
+    }
+}
+
+impl fmt::Display for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn {}>", self.name)
+    }
+}
+
+impl Callable for NativeFunction {
+    fn arity(&self) -> usize {
+        self.arity
+    }
+
+    fn max_arity(&self) -> Option<usize> {
+        self.max_arity
+    }
+
+    fn param_names(&self) -> Vec<String> {
+        self.params.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn capabilities(&self) -> Vec<Capability> {
+        self.capabilities.clone()
+    }
+
+    fn call(&self, interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+        // 由其他本地函数直接调用时不经过调用表达式的检查，须在此处检查能力
+        if let Some(capability) = interpreter.missing_capability(&self.capabilities) {
+            return Err(capability_error(self, capability));
+        }
+        // 错误信息加上函数名
+        (self.func)(interpreter, arguments).map_err(|e| match e {
+            LoxResult::NativeError { message } => {
+                LoxResult::native_error(format!("{}: {}", self.name, message))
+            }
+            e => e,
+        })
+    }
+}
+
+// 可转换为本地函数的 Rust 函数，Args 为参数类型组成的元组
+pub trait IntoNative<Args> {
+    fn arity() -> usize;
+
+    fn invoke(&self, interpreter: &Interpreter, arguments: &[Object]) -> Result<Object, LoxResult>;
+}
+
+macro_rules! impl_into_native {
+    ($count:expr; $($arg:ident : $index:expr),*) => {
+        impl<F, R, $($arg),*> IntoNative<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> R,
+            R: IntoLox,
+            $($arg: FromLox,)*
+        {
+            fn arity() -> usize {
+                $count
+            }
+
+            // 宿主函数自行分配内存，结果在转换为 Lox 值之前计入预算，超出时不再转换
+            #[allow(unused_variables)]
+            fn invoke(
+                &self,
+                interpreter: &Interpreter,
+                arguments: &[Object],
+            ) -> Result<Object, LoxResult> {
+                let value = self($(argument::<$arg>(arguments, $index)?),*);
+                reserve(interpreter, value.size())?;
+                Ok(value.into_lox())
+            }
+        }
+    };
+}
+
+impl_into_native!(0;);
+impl_into_native!(1; A: 0);
+impl_into_native!(2; A: 0, B: 1);
+impl_into_native!(3; A: 0, B: 1, C: 2);
+impl_into_native!(4; A: 0, B: 1, C: 2, D: 3);
+
+pub(crate) fn argument<T: FromLox>(arguments: &[Object], index: usize) -> Result<T, LoxResult> {
+    T::from_lox(&arguments[index]).ok_or_else(|| type_error(&T::lox_type(), arguments, index))
+}
+
+fn type_error(expect: &str, arguments: &[Object], index: usize) -> LoxResult {
+    LoxResult::native_error(format!(
+        "expect {} as argument {} but got {}",
+        expect,
+        index + 1,
+        arguments[index].type_name()
+    ))
+}
+
+pub(crate) fn expect_str(arguments: &[Object], index: usize) -> Result<&str, LoxResult> {
+    match &arguments[index] {
+        Object::Str(s) => Ok(s),
+        _ => Err(type_error("string", arguments, index)),
+    }
+}
+
+// 非负整数，用于下标及长度
+pub(crate) fn expect_index(arguments: &[Object], index: usize) -> Result<usize, LoxResult> {
+    match arguments[index] {
+        Object::Number(num) if num >= 0.0 && num.fract() == 0.0 => Ok(num as usize),
+        _ => Err(type_error("non-negative integer", arguments, index)),
+    }
+}
+
+fn capability_error(f: &dyn Callable, capability: Capability) -> LoxResult {
+    LoxResult::native_error(format!(
+        "{} requires capability `{}`",
+        f.to_string(),
+        capability
+    ))
+}
+
+fn reserve(interpreter: &Interpreter, bytes: usize) -> Result<(), LoxResult> {
+    interpreter.reserve(bytes).map_err(LoxResult::native_error)
+}
+
+// 先计入内存预算再复制字符串
+pub(crate) fn new_str(interpreter: &Interpreter, s: &str) -> Result<Object, LoxResult> {
+    reserve(interpreter, s.len())?;
+    Ok(Object::Str(s.to_string()))
+}
+
+// 按结果长度的上限预留内存后再构造字符串，多预留的部分随后归还
+pub(crate) fn build_str<F>(
+    interpreter: &Interpreter,
+    bound: usize,
+    build: F,
+) -> Result<Object, LoxResult>
+where
+    F: FnOnce() -> Result<String, LoxResult>,
+{
+    reserve(interpreter, bound)?;
+    let result = build();
+    let used = result.as_ref().map_or(0, |s| s.len().min(bound));
+    interpreter.release(bound - used);
+
+    result.map(Object::Str)
+}
+
+// 为 len 个元素的列表预留内存，须在构造列表之前调用
+pub(crate) fn reserve_list(interpreter: &Interpreter, len: usize) -> Result<(), LoxResult> {
+    reserve(interpreter, len.saturating_mul(mem::size_of::<Object>()))
+}
+
+// 长度无法预先确定的字符串，每次写入前计入内存预算
+pub(crate) struct StrBuilder<'a> {
+    interpreter: &'a Interpreter,
+    buf: String,
+    error: Option<String>,
+}
+
+impl<'a> StrBuilder<'a> {
+    pub fn new(interpreter: &'a Interpreter) -> Self {
+        Self {
+            interpreter,
+            buf: String::new(),
+            error: None,
+        }
+    }
+
+    // 追加值的打印形式，超出内存预算时停止写入
+    pub fn push(&mut self, value: impl fmt::Display) -> Result<(), LoxResult> {
+        match write!(self, "{}", value) {
+            Ok(()) => Ok(()),
+            Err(_) => Err(LoxResult::native_error(
+                self.error.take().unwrap_or_default(),
+            )),
+        }
+    }
+
+    pub fn finish(self) -> Object {
+        Object::Str(self.buf)
+    }
+}
+
+impl fmt::Write for StrBuilder<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if let Err(message) = self.interpreter.reserve(s.len()) {
+            self.error = Some(message);
+            return Err(fmt::Error);
+        }
+        self.buf.push_str(s);
+
+        Ok(())
+    }
+}
+
 #[derive(Default)]
 pub struct NativeClock {}
 
@@ -13,9 +277,9 @@ impl NativeClock {
     }
 }
 
-impl ToString for NativeClock {
-    fn to_string(&self) -> String {
-        "<func clock>".to_string()
+impl fmt::Display for NativeClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn clock>")
     }
 }
 
@@ -24,11 +288,18 @@ impl Callable for NativeClock {
         0
     }
 
+    fn capabilities(&self) -> Vec<Capability> {
+        vec![Capability::Clock]
+    }
+
     fn call(
         &self,
-        _interpreter: &Interpreter,
+        interpreter: &Interpreter,
         _arguments: Vec<Object>,
     ) -> Result<Object, LoxResult> {
+        if let Some(capability) = interpreter.missing_capability(&self.capabilities()) {
+            return Err(capability_error(self, capability));
+        }
         match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
             Ok(n) => Ok(Object::Number(n.as_millis() as f64)),
             Err(e) => Err(LoxResult::system_error(format!(
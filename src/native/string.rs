@@ -0,0 +1,274 @@
+use crate::core::Object;
+use crate::error::LoxResult;
+use crate::interpreter::Interpreter;
+
+use super::{
+    build_str, expect_index, expect_str, new_str, reserve_list, NativeFunction, StrBuilder,
+};
+
+// 字符串相关的本地函数，下标均以 Unicode 字符计
+pub fn natives() -> Vec<NativeFunction> {
+    vec![
+        NativeFunction::new("len", 1, len),
+        NativeFunction::new("substr", 2, substr)
+            .optional(1)
+            .params(&["s", "start", "end"]),
+        NativeFunction::new("index_of", 2, index_of).params(&["s", "pattern"]),
+        NativeFunction::new("split", 2, split).params(&["s", "separator"]),
+        NativeFunction::new("join", 2, join).params(&["list", "separator"]),
+        NativeFunction::new("upper", 1, upper),
+        NativeFunction::new("lower", 1, lower),
+        NativeFunction::new("trim", 1, trim),
+        NativeFunction::new("replace", 3, replace).params(&["s", "old", "new"]),
+        NativeFunction::new("starts_with", 2, starts_with),
+        NativeFunction::new("ends_with", 2, ends_with),
+        NativeFunction::new("chars", 1, chars),
+        NativeFunction::new("str", 1, str),
+        NativeFunction::new("num", 1, num),
+    ]
+}
+
+fn len(_interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let len = match &arguments[0] {
+        Object::Str(s) => s.chars().count(),
+        Object::List(items) => items.borrow().len(),
+        other => {
+            return Err(LoxResult::native_error(format!(
+                "expect string or list as argument 1 but got {}",
+                other.type_name()
+            )))
+        }
+    };
+
+    Ok(Object::Number(len as f64))
+}
+
+// substr(s, start, end)，end 缺省时截取到末尾
+fn substr(interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let s = expect_str(&arguments, 0)?;
+    let count = s.chars().count();
+    let start = expect_index(&arguments, 1)?;
+    let end = match arguments.get(2) {
+        Some(Object::Nil) | None => count,
+        Some(_) => expect_index(&arguments, 2)?,
+    };
+    if start > end || end > count {
+        return Err(LoxResult::native_error(format!(
+            "range {}..{} out of bounds for string of length {}",
+            start, end, count
+        )));
+    }
+
+    build_str(interpreter, s.len(), || {
+        Ok(s.chars().skip(start).take(end - start).collect())
+    })
+}
+
+// 未找到时返回 -1
+fn index_of(_interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let s = expect_str(&arguments, 0)?;
+    let sub = expect_str(&arguments, 1)?;
+    let index = match s.find(sub) {
+        Some(byte_index) => s[..byte_index].chars().count() as f64,
+        None => -1.0,
+    };
+
+    Ok(Object::Number(index))
+}
+
+fn split(interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let s = expect_str(&arguments, 0)?;
+    let separator = expect_str(&arguments, 1)?;
+    if separator.is_empty() {
+        return Err(LoxResult::native_error(
+            "separator must not be empty".to_string(),
+        ));
+    }
+
+    reserve_list(interpreter, s.matches(separator).count() + 1)?;
+    let parts = s
+        .split(separator)
+        .map(|part| new_str(interpreter, part))
+        .collect::<Result<Vec<Object>, LoxResult>>()?;
+    Ok(Object::new_list(parts))
+}
+
+// 列表中的元素按其打印形式拼接
+fn join(interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let items = match &arguments[0] {
+        Object::List(items) => items.clone(),
+        other => {
+            return Err(LoxResult::native_error(format!(
+                "expect list as argument 1 but got {}",
+                other.type_name()
+            )))
+        }
+    };
+    let separator = expect_str(&arguments, 1)?;
+    let mut joined = StrBuilder::new(interpreter);
+    for (i, item) in items.borrow().iter().enumerate() {
+        if i > 0 {
+            joined.push(separator)?;
+        }
+        joined.push(item)?;
+    }
+
+    Ok(joined.finish())
+}
+
+// 大小写转换后每个字节最多变为 3 个字节
+fn upper(interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let s = expect_str(&arguments, 0)?;
+    build_str(interpreter, s.len().saturating_mul(3), || {
+        Ok(s.to_uppercase())
+    })
+}
+
+fn lower(interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let s = expect_str(&arguments, 0)?;
+    build_str(interpreter, s.len().saturating_mul(3), || {
+        Ok(s.to_lowercase())
+    })
+}
+
+fn trim(interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    new_str(interpreter, expect_str(&arguments, 0)?.trim())
+}
+
+fn replace(interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let s = expect_str(&arguments, 0)?;
+    let from = expect_str(&arguments, 1)?;
+    let to = expect_str(&arguments, 2)?;
+
+    // 空的 from 匹配每个字符的前后
+    let count = match from.is_empty() {
+        true => s.chars().count() + 1,
+        false => s.matches(from).count(),
+    };
+    let len = (s.len() - count * from.len()).saturating_add(count.saturating_mul(to.len()));
+    build_str(interpreter, len, || Ok(s.replace(from, to)))
+}
+
+fn starts_with(_interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let s = expect_str(&arguments, 0)?;
+    Ok(Object::Bool(s.starts_with(expect_str(&arguments, 1)?)))
+}
+
+fn ends_with(_interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let s = expect_str(&arguments, 0)?;
+    Ok(Object::Bool(s.ends_with(expect_str(&arguments, 1)?)))
+}
+
+fn chars(interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let s = expect_str(&arguments, 0)?;
+    reserve_list(interpreter, s.chars().count())?;
+    let chars = s
+        .chars()
+        .map(|c| new_str(interpreter, c.encode_utf8(&mut [0; 4])))
+        .collect::<Result<Vec<Object>, LoxResult>>()?;
+    Ok(Object::new_list(chars))
+}
+
+fn str(interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    match &arguments[0] {
+        Object::Str(s) => new_str(interpreter, s),
+        other => {
+            let mut s = StrBuilder::new(interpreter);
+            s.push(other)?;
+            Ok(s.finish())
+        }
+    }
+}
+
+// 无法解析的字符串返回 nil
+fn num(_interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    match &arguments[0] {
+        Object::Number(num) => Ok(Object::Number(*num)),
+        Object::Str(s) => Ok(s
+            .trim()
+            .parse::<f64>()
+            .map(Object::Number)
+            .unwrap_or(Object::Nil)),
+        other => Err(LoxResult::native_error(format!(
+            "expect string or number as argument 1 but got {}",
+            other.type_name()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::core::Object;
+    use crate::error::LoxResult;
+    use crate::test_util::{eval, string};
+
+    #[test]
+    fn test_unicode_indexing() {
+        assert_eq!(eval("len(\"héllo世界\")").unwrap(), Object::Number(7.0));
+        assert_eq!(eval("substr(\"héllo世界\", 1, 3)").unwrap(), string("él"));
+        assert_eq!(eval("substr(\"héllo世界\", 5)").unwrap(), string("世界"));
+        assert_eq!(
+            eval("index_of(\"héllo世界\", \"界\")").unwrap(),
+            Object::Number(6.0)
+        );
+        assert_eq!(
+            eval("index_of(\"abc\", \"x\")").unwrap(),
+            Object::Number(-1.0)
+        );
+        assert_eq!(
+            eval("chars(\"a世\")").unwrap(),
+            Object::new_list(vec![string("a"), string("世")])
+        );
+    }
+
+    #[test]
+    fn test_string_functions() {
+        assert_eq!(
+            eval("join(split(\"a,b,c\", \",\"), \"-\")").unwrap(),
+            string("a-b-c")
+        );
+        assert_eq!(eval("upper(\"abc\")").unwrap(), string("ABC"));
+        assert_eq!(eval("lower(\"ABC\")").unwrap(), string("abc"));
+        assert_eq!(eval("trim(\"  a b \")").unwrap(), string("a b"));
+        assert_eq!(
+            eval("replace(\"aXbX\", \"X\", \"-\")").unwrap(),
+            string("a-b-")
+        );
+        assert_eq!(
+            eval("starts_with(\"lox\", \"lo\")").unwrap(),
+            Object::Bool(true)
+        );
+        assert_eq!(
+            eval("ends_with(\"lox\", \"lo\")").unwrap(),
+            Object::Bool(false)
+        );
+        assert_eq!(eval("str(1.5)").unwrap(), string("1.5"));
+        assert_eq!(eval("num(\" 42 \")").unwrap(), Object::Number(42.0));
+        assert_eq!(eval("num(\"abc\")").unwrap(), Object::Nil);
+    }
+
+    #[test]
+    fn test_errors_at_call() {
+        match eval("\n\nlen(1)") {
+            Err(LoxResult::RuntimeError { token, message, .. }) => {
+                assert_eq!(token.line, 3);
+                assert_eq!(
+                    message,
+                    "len: expect string or list as argument 1 but got number"
+                );
+            }
+            other => panic!("expect runtime error, got {:?}", other),
+        }
+        match eval("substr(\"abc\", 2, 5)") {
+            Err(LoxResult::RuntimeError { message, .. }) => assert_eq!(
+                message,
+                "substr: range 2..5 out of bounds for string of length 3"
+            ),
+            other => panic!("expect runtime error, got {:?}", other),
+        }
+        assert!(matches!(
+            eval("substr(\"abc\", -1)"),
+            Err(LoxResult::RuntimeError { .. })
+        ));
+    }
+}
@@ -0,0 +1,101 @@
+use std::env;
+
+use crate::core::{Capability, Object};
+use crate::error::LoxResult;
+use crate::interpreter::Interpreter;
+
+use super::{argument, expect_str, new_str, NativeFunction};
+
+// 环境变量及进程相关的本地函数，需要解释器授予相应能力
+pub fn natives() -> Vec<NativeFunction> {
+    vec![
+        NativeFunction::new("getenv", 1, getenv).requires(Capability::Env),
+        NativeFunction::new("exit", 0, exit)
+            .optional(1)
+            .params(&["code"])
+            .requires(Capability::Exit),
+    ]
+}
+
+// 变量不存在或不是合法的 Unicode 时返回 nil
+fn getenv(interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let name = expect_str(&arguments, 0)?;
+    match env::var(name) {
+        Ok(value) => new_str(interpreter, &value),
+        Err(_) => Ok(Object::Nil),
+    }
+}
+
+// 不直接结束进程，由宿主决定如何处理退出码
+fn exit(_interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let code = match arguments.first() {
+        Some(Object::Nil) | None => 0,
+        Some(_) => {
+            let code: i64 = argument(&arguments, 0)?;
+            i32::try_from(code)
+                .map_err(|_| LoxResult::native_error(format!("exit code {} out of range", code)))?
+        }
+    };
+
+    Err(LoxResult::exit(code))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::core::{Capabilities, Capability, Object};
+    use crate::error::LoxResult;
+    use crate::interpreter::Interpreter;
+    use crate::test_util::{get, run, string};
+
+    #[test]
+    fn test_getenv() {
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "var path = getenv(\"PATH\"); var missing = getenv(\"LOX_NO_SUCH_VARIABLE\");",
+        )
+        .unwrap();
+        assert_eq!(
+            get(&interpreter, "path"),
+            string(&std::env::var("PATH").unwrap())
+        );
+        assert_eq!(get(&interpreter, "missing"), Object::Nil);
+    }
+
+    #[test]
+    fn test_exit() {
+        let interpreter = Interpreter::new();
+        // 退出不能被 catch 捕获，之后的语句不再执行
+        let result = run(
+            &interpreter,
+            "var after = false; try { exit(3); } catch (e) {} after = true;",
+        );
+        assert!(matches!(result, Err(LoxResult::Exit { code: 3 })));
+        assert_eq!(get(&interpreter, "after"), Object::Bool(false));
+
+        assert!(matches!(
+            run(&interpreter, "exit();"),
+            Err(LoxResult::Exit { code: 0 })
+        ));
+        assert!(matches!(
+            run(&interpreter, "exit(4294967296);"),
+            Err(LoxResult::RuntimeError { message, .. })
+                if message == "exit: exit code 4294967296 out of range"
+        ));
+    }
+
+    #[test]
+    fn test_system_capabilities() {
+        let interpreter =
+            Interpreter::with_capabilities(Capabilities::all().without(Capability::Exit));
+        assert!(run(&interpreter, "getenv(\"PATH\");").is_ok());
+        assert!(matches!(
+            run(&interpreter, "exit(1);"),
+            Err(LoxResult::RuntimeError { message, .. })
+                if message == "<native fn exit> requires capability `exit`"
+        ));
+
+        let interpreter = Interpreter::with_capabilities(Capabilities::none());
+        assert!(run(&interpreter, "getenv(\"PATH\");").is_err());
+    }
+}
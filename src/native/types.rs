@@ -0,0 +1,86 @@
+use crate::core::{Callable, Object};
+use crate::error::LoxResult;
+use crate::interpreter::Interpreter;
+
+use super::{new_str, NativeFunction, StrBuilder};
+
+// 类型查询及转换相关的本地函数
+pub fn natives() -> Vec<NativeFunction> {
+    vec![
+        NativeFunction::new("type", 1, type_of),
+        NativeFunction::new("is_callable", 1, is_callable),
+        NativeFunction::new("arity", 1, arity),
+        NativeFunction::new("to_string", 1, to_string),
+    ]
+}
+
+fn type_of(interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    new_str(interpreter, arguments[0].type_name())
+}
+
+fn is_callable(_interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    Ok(Object::Bool(matches!(arguments[0], Object::Func(_))))
+}
+
+// 必填参数的个数
+fn arity(_interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    match &arguments[0] {
+        Object::Func(f) => Ok(Object::Number(f.arity() as f64)),
+        other => Err(LoxResult::native_error(format!(
+            "expect function as argument 1 but got {}",
+            other.type_name()
+        ))),
+    }
+}
+
+// 与 print 的输出一致
+fn to_string(interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let mut s = StrBuilder::new(interpreter);
+    s.push(&arguments[0])?;
+    Ok(s.finish())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::core::Object;
+    use crate::interpreter::Interpreter;
+    use crate::test_util::{get, run, string};
+
+    #[test]
+    fn test_type() {
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "func f(a, b = 1) {}
+             var types = type(1) + \",\" + type(\"s\") + \",\" + type(nil) + \",\" + type(true)
+                 + \",\" + type(f) + \",\" + type(clock);",
+        )
+        .unwrap();
+        assert_eq!(
+            get(&interpreter, "types"),
+            string("number,string,nil,bool,function,function")
+        );
+    }
+
+    #[test]
+    fn test_introspection() {
+        let interpreter = Interpreter::new();
+        run(
+            &interpreter,
+            "func f(a, b = 1) {}
+             var callable = is_callable(f); var not_callable = is_callable(1);
+             var n = arity(f); var m = arity(len);
+             var s1 = to_string(f); var s2 = to_string(clock); var s3 = to_string(1.5);",
+        )
+        .unwrap();
+        assert_eq!(get(&interpreter, "callable"), Object::Bool(true));
+        assert_eq!(get(&interpreter, "not_callable"), Object::Bool(false));
+        assert_eq!(get(&interpreter, "n"), Object::Number(1.0));
+        assert_eq!(get(&interpreter, "m"), Object::Number(1.0));
+        assert_eq!(get(&interpreter, "s1"), string("<fn f>"));
+        assert_eq!(get(&interpreter, "s2"), string("<native fn clock>"));
+        assert_eq!(get(&interpreter, "s3"), string("1.5"));
+
+        assert!(run(&interpreter, "arity(1);").is_err());
+    }
+}
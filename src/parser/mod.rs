@@ -1,3 +1,7 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::expr::Expr;
 use crate::stmt::*;
 use crate::token_type::TokenType;
 use crate::{error::LoxResult, token::Token};
@@ -5,9 +9,21 @@ use crate::{error::LoxResult, token::Token};
 mod parser_expr;
 mod parser_stmt;
 
+// 形参列表：形参名、对应的默认值以及剩余参数
+#[derive(Default)]
+struct Parameters {
+    params: Vec<Token>,
+    defaults: Vec<Option<Rc<Expr>>>,
+    rest: Option<Token>,
+}
+
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    // 当前所在函数的嵌套层数，只有函数内的 return 才能作为尾调用
+    function_depth: usize,
+    // 每个 `(` 的下标到与之匹配的 `)` 的下标，用于判断箭头函数
+    closing_parens: HashMap<usize, usize>,
 }
 
 impl Parser {
@@ -16,10 +32,32 @@ impl Parser {
         for token in tokens {
             own_tokens.push(token.clone())
         }
+        let closing_parens = Self::match_parens(&own_tokens);
         Self {
             tokens: own_tokens,
             current: 0,
+            function_depth: 0,
+            closing_parens,
+        }
+    }
+
+    // 一次扫描找出所有匹配的括号，不匹配的 `(` 没有对应项
+    fn match_parens(tokens: &[Token]) -> HashMap<usize, usize> {
+        let mut closing = HashMap::new();
+        let mut open = Vec::new();
+        for (index, token) in tokens.iter().enumerate() {
+            match token.tk_type {
+                TokenType::LeftParen => open.push(index),
+                TokenType::RightParen => {
+                    if let Some(start) = open.pop() {
+                        closing.insert(start, index);
+                    }
+                }
+                _ => (),
+            }
         }
+
+        closing
     }
 
     pub fn parse(&mut self) -> Result<Vec<Stmt>, LoxResult> {
@@ -55,6 +93,14 @@ impl Parser {
         !self.is_at_end() && self.peek().unwrap().tk_type == token_type
     }
 
+    // 向后多看一个 token
+    fn is_expect_next(&self, token_type: TokenType) -> bool {
+        match self.tokens.get(self.current + 1) {
+            None => false,
+            Some(token) => token.tk_type == token_type,
+        }
+    }
+
     fn is_at_end(&self) -> bool {
         match self.peek() {
             None => true,
@@ -87,11 +133,17 @@ impl Parser {
                 TokenType::Class
                 | TokenType::Func
                 | TokenType::Var
+                | TokenType::Const
                 | TokenType::For
                 | TokenType::If
                 | TokenType::While
                 | TokenType::Print
-                | TokenType::Return => return,
+                | TokenType::Return
+                | TokenType::Throw
+                | TokenType::Try
+                | TokenType::Import
+                | TokenType::From
+                | TokenType::Export => return,
                 _ => (),
             }
 
@@ -3,6 +3,8 @@ use std::rc::Rc;
 use crate::core::*;
 use crate::error::LoxResult;
 use crate::expr::*;
+use crate::stmt::*;
+use crate::token::Token;
 use crate::token_type::TokenType;
 
 use super::Parser;
@@ -30,8 +32,9 @@ impl Parser {
     //     }
     // }
     // assignment     → IDENTIFIER "=" assignment | logic_or ;
+    // assignment     → IDENTIFIER ( "=" | "+=" | "-=" | "*=" | "/=" ) assignment | conditional ;
     fn assignment(&mut self) -> Result<Expr, LoxResult> {
-        let expr = self.logic_or()?;
+        let expr = self.conditional()?;
         if self.is_match(&vec![TokenType::Equal]) {
             let equals = self.previous().unwrap();
             if let Expr::Variable(e) = expr {
@@ -47,9 +50,70 @@ impl Parser {
             return Err(LoxResult::parse_error(equals, "invalid assign".to_string()));
         }
 
+        let compound_operators = vec![
+            TokenType::PlusEqual,
+            TokenType::MinusEqual,
+            TokenType::StarEqual,
+            TokenType::SlashEqual,
+        ];
+        if self.is_match(&compound_operators) {
+            let operator = self.previous().unwrap();
+            // 目前只有变量可以作为赋值目标
+            if let Expr::Variable(e) = expr {
+                let value = self.assignment()?;
+
+                return Ok(Expr::CompoundAssign(CompoundAssignExpr {
+                    name: e.name,
+                    operator,
+                    value: Rc::new(value),
+                }));
+            }
+
+            return Err(LoxResult::parse_error(
+                operator,
+                "invalid assign".to_string(),
+            ));
+        }
+
         Ok(expr)
     }
 
+    // conditional    → coalesce ( "?" expression ":" conditional )? ;
+    fn conditional(&mut self) -> Result<Expr, LoxResult> {
+        let condition = self.coalesce()?;
+
+        if self.is_match(&vec![TokenType::Question]) {
+            let then_branch = self.expression()?;
+            self.consume(TokenType::Colon, "expect `:` after then branch")?;
+            let else_branch = self.conditional()?;
+
+            return Ok(Expr::Conditional(ConditionalExpr {
+                condition: Rc::new(condition),
+                then_branch: Rc::new(then_branch),
+                else_branch: Rc::new(else_branch),
+            }));
+        }
+
+        Ok(condition)
+    }
+
+    // coalesce       → logic_or ( "??" logic_or )* ;
+    fn coalesce(&mut self) -> Result<Expr, LoxResult> {
+        let mut left = self.logic_or()?;
+
+        while self.is_match(&vec![TokenType::QuestionQuestion]) {
+            let operator = self.previous().unwrap();
+            let right = self.logic_or()?;
+            left = Expr::Logical(LogicalExpr {
+                left: Rc::new(left),
+                operator,
+                right: Rc::new(right),
+            });
+        }
+
+        Ok(left)
+    }
+
     // logic_or       → logic_and ( "or" logic_and )*
     fn logic_or(&mut self) -> Result<Expr, LoxResult> {
         let mut left = self.logic_and()?;
@@ -159,6 +223,7 @@ impl Parser {
 
     // unary          → ( "!" | "-" ) unary | primary ;
     // unary          → ( "!" | "-" ) unary | call
+    // unary          → ( "!" | "-" ) unary | ( "++" | "--" ) unary | postfix ;
     fn unary(&mut self) -> Result<Expr, LoxResult> {
         let unary_operators = vec![TokenType::Bang, TokenType::Minus];
         if self.is_match(&unary_operators) {
@@ -168,12 +233,43 @@ impl Parser {
                 operator,
                 right: Rc::new(right),
             }))
+        } else if self.is_match(&vec![TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let operator = self.previous().unwrap();
+            let target = self.unary()?;
+            Self::update(target, operator, true)
+        } else {
+            self.postfix()
+        }
+    }
+
+    // postfix        → call ( "++" | "--" )? ;
+    fn postfix(&mut self) -> Result<Expr, LoxResult> {
+        let expr = self.call()?;
+        if self.is_match(&vec![TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let operator = self.previous().unwrap();
+            return Self::update(expr, operator, false);
+        }
+
+        Ok(expr)
+    }
+
+    fn update(target: Expr, operator: Token, prefix: bool) -> Result<Expr, LoxResult> {
+        if let Expr::Variable(e) = target {
+            Ok(Expr::Update(UpdateExpr {
+                name: e.name,
+                operator,
+                prefix,
+            }))
         } else {
-            self.call()
+            Err(LoxResult::parse_error(
+                operator,
+                "invalid increment/decrement target".to_string(),
+            ))
         }
     }
 
     // call           → primary ( "(" arguments? ")" )* ;
+    // call           → primary ( "(" arguments? ")" | "." IDENTIFIER )* ;
     fn call(&mut self) -> Result<Expr, LoxResult> {
         let mut expr = self.primary()?;
 
@@ -190,6 +286,12 @@ impl Parser {
         loop {
             if self.is_match(&vec![TokenType::LeftParen]) {
                 expr = self.finish_call(Rc::new(expr))?;
+            } else if self.is_match(&vec![TokenType::Dot]) {
+                let name = self.consume(TokenType::Identifier, "expect property name after `.`")?;
+                expr = Expr::Get(GetExpr {
+                    object: Rc::new(expr),
+                    name,
+                });
             } else {
                 break;
             }
@@ -199,8 +301,9 @@ impl Parser {
     }
     fn finish_call(&mut self, callee: Rc<Expr>) -> Result<Expr, LoxResult> {
         let mut arguments: Vec<Rc<Expr>> = vec![];
+        let mut names: Vec<Token> = vec![];
         if !self.is_expect(TokenType::RightParen) {
-            arguments = self.arguments()?;
+            (arguments, names) = self.arguments()?;
         }
         let paren = self.consume(TokenType::RightParen, "expect `)`")?;
 
@@ -208,38 +311,130 @@ impl Parser {
             callee,
             paren,
             arguments,
+            names,
         }))
     }
 
     // arguments      → expression ( "," expression )*
-    fn arguments(&mut self) -> Result<Vec<Rc<Expr>>, LoxResult> {
+    // arguments      → argument ( "," argument )* ;
+    // argument       → ( IDENTIFIER ":" )? expression ;
+    // 命名参数须位于位置参数之后，names 依次对应 arguments 末尾的命名参数
+    fn arguments(&mut self) -> Result<(Vec<Rc<Expr>>, Vec<Token>), LoxResult> {
         let mut arguments: Vec<Rc<Expr>> = vec![];
-        arguments.push(Rc::new(self.expression()?));
-        while self.is_match(&vec![TokenType::Comma]) {
+        let mut names: Vec<Token> = vec![];
+        loop {
             if arguments.len() >= 255 {
                 return Err(LoxResult::parse_error(
                     self.peek().unwrap(),
                     "can't have more than 255 arguments".to_string(),
                 ));
             }
+
+            if self.is_expect(TokenType::Identifier) && self.is_expect_next(TokenType::Colon) {
+                let name = self.consume(TokenType::Identifier, "expect argument name")?;
+                self.advance();
+                if names.iter().any(|n| n.lexeme == name.lexeme) {
+                    return Err(LoxResult::parse_error(
+                        name.clone(),
+                        format!("duplicate argument `{}`", name.lexeme),
+                    ));
+                }
+                names.push(name);
+            } else if !names.is_empty() {
+                return Err(LoxResult::parse_error(
+                    self.peek().unwrap(),
+                    "positional argument follows named argument".to_string(),
+                ));
+            }
             arguments.push(Rc::new(self.expression()?));
+
+            if !self.is_match(&vec![TokenType::Comma]) {
+                break;
+            }
         }
 
-        Ok(arguments)
+        Ok((arguments, names))
+    }
+
+    // lambda         → "func" "(" parameters? ")" block ;
+    fn lambda(&mut self) -> Result<Expr, LoxResult> {
+        let keyword = self.previous().unwrap();
+        let name = Self::lambda_name(&keyword);
+        let declaration = self.function_body(name, "lambda")?;
+
+        Ok(Expr::Lambda(LambdaExpr {
+            declaration: Rc::new(declaration),
+        }))
+    }
+
+    // arrow          → "(" parameters? ")" "=>" ( block | expression ) ;
+    fn arrow(&mut self) -> Result<Expr, LoxResult> {
+        let paren = self.consume(TokenType::LeftParen, "expect `(` before parameters")?;
+        let parameters = self.parameter_list()?;
+        self.consume(TokenType::RightParen, "expect `)` after parameters")?;
+        let arrow = self.consume(TokenType::Arrow, "expect `=>` after parameters")?;
+
+        // `=> expr` 等价于 `{ return expr; }`
+        self.function_depth += 1;
+        let body = self.arrow_body(arrow);
+        self.function_depth -= 1;
+        let body = body?;
+
+        Ok(Expr::Lambda(LambdaExpr {
+            declaration: Rc::new(FunctionStmt {
+                name: Self::lambda_name(&paren),
+                params: Rc::new(parameters.params),
+                defaults: Rc::new(parameters.defaults),
+                rest: parameters.rest,
+                body: Rc::new(body),
+            }),
+        }))
+    }
+
+    fn arrow_body(&mut self, arrow: Token) -> Result<Vec<Rc<Stmt>>, LoxResult> {
+        if self.is_match(&vec![TokenType::LeftBrace]) {
+            return self.block();
+        }
+
+        let value = Some(Rc::new(self.expression()?));
+        let tail_call = self.is_tail_call(&value);
+        Ok(vec![Rc::new(Stmt::Return(ReturnStmt {
+            name: arrow,
+            value,
+            tail_call,
+        }))])
+    }
+
+    fn lambda_name(token: &Token) -> Token {
+        Token::new(
+            TokenType::Identifier,
+            "lambda".to_string(),
+            None,
+            token.line,
+        )
+    }
+
+    // 与当前 `(` 匹配的 `)` 之后紧跟 `=>` 则为箭头函数
+    fn is_arrow(&self) -> bool {
+        match self.closing_parens.get(&self.current) {
+            Some(&index) => matches!(
+                self.tokens.get(index + 1),
+                Some(token) if token.is(TokenType::Arrow)
+            ),
+            None => false,
+        }
     }
 
     // primary        → NUMBER | STRING | "true" | "false" | "nil" | "(" expression ")" ;
+    // primary        → ... | lambda | arrow ;
     fn primary(&mut self) -> Result<Expr, LoxResult> {
         if self.is_match(&vec![TokenType::Number, TokenType::String]) {
             let value = self.previous().unwrap().literal.unwrap();
             Ok(Expr::Literal(LiteralExpr { value }))
-        } else if self.is_match(&vec![TokenType::False]) {
-            Ok(Expr::Literal(LiteralExpr {
-                value: Object::False,
-            }))
-        } else if self.is_match(&vec![TokenType::True]) {
+        } else if self.is_match(&vec![TokenType::False, TokenType::True]) {
+            let value = self.previous().unwrap().is(TokenType::True);
             Ok(Expr::Literal(LiteralExpr {
-                value: Object::True,
+                value: Object::Bool(value),
             }))
         } else if self.is_match(&vec![TokenType::Nil]) {
             Ok(Expr::Literal(LiteralExpr { value: Object::Nil }))
@@ -247,6 +442,10 @@ impl Parser {
             Ok(Expr::Variable(VariableExpr {
                 name: self.previous().unwrap(),
             }))
+        } else if self.is_match(&vec![TokenType::Func]) {
+            self.lambda()
+        } else if self.is_expect(TokenType::LeftParen) && self.is_arrow() {
+            self.arrow()
         } else if self.is_match(&vec![TokenType::LeftParen]) {
             let expr = self.expression()?;
             self.consume(TokenType::RightParen, "expect `)` after expression")?;
@@ -1,6 +1,6 @@
 use std::rc::Rc;
 
-use super::Parser;
+use super::{Parameters, Parser};
 
 use crate::core::*;
 use crate::error::LoxResult;
@@ -10,23 +10,55 @@ use crate::token::Token;
 use crate::token_type::TokenType;
 
 impl Parser {
-    //  program        → declaration* EOF ;
+    //  program        → topLevelDecl* EOF ;
     pub fn program(&mut self) -> Result<Vec<Stmt>, LoxResult> {
         let mut statements: Vec<Stmt> = vec![];
         while !self.is_at_end() {
-            statements.push(self.declaration()?);
+            statements.push(self.top_level_declaration()?);
         }
 
         Ok(statements)
     }
 
+    // topLevelDecl   → "export" ( funDecl | varDecl ) | declaration ;
+    fn top_level_declaration(&mut self) -> Result<Stmt, LoxResult> {
+        if !self.is_match(&vec![TokenType::Export]) {
+            return self.declaration();
+        }
+
+        let keyword = self.previous().unwrap();
+        let is_declaration = self.is_expect(TokenType::Var)
+            || self.is_expect(TokenType::Const)
+            || (self.is_expect(TokenType::Func) && self.is_expect_next(TokenType::Identifier));
+        if !is_declaration {
+            return Err(LoxResult::parse_error(
+                keyword,
+                "expect variable or function declaration after `export`".to_string(),
+            ));
+        }
+
+        Ok(Stmt::Export(ExportStmt {
+            keyword,
+            declaration: Rc::new(self.declaration()?),
+        }))
+    }
+
     //  declaration    → varDecl | statement ;
     //  declaration    → funDecl | varDecl | statement ;
     fn declaration(&mut self) -> Result<Stmt, LoxResult> {
-        let result = if self.is_match(&vec![TokenType::Var]) {
+        // `func (` 开头的是匿名函数表达式，交由 statement 处理
+        let result = if self.is_match(&vec![TokenType::Var, TokenType::Const]) {
             self.var_declaration()
-        } else if self.is_match(&vec![TokenType::Func]) {
+        } else if self.is_expect(TokenType::Func) && self.is_expect_next(TokenType::Identifier) {
+            self.advance();
             self.func_declaration()
+        } else if self.is_match(&vec![TokenType::Import, TokenType::From]) {
+            self.import_declaration()
+        } else if self.is_match(&vec![TokenType::Export]) {
+            Err(LoxResult::parse_error(
+                self.previous().unwrap(),
+                "`export` is only allowed at the top level".to_string(),
+            ))
         } else {
             self.statement()
         };
@@ -38,6 +70,43 @@ impl Parser {
         result
     }
 
+    // importDecl     → "import" STRING "as" IDENTIFIER ";"
+    //                | "from" STRING "import" IDENTIFIER ( "," IDENTIFIER )* ";" ;
+    fn import_declaration(&mut self) -> Result<Stmt, LoxResult> {
+        let keyword = self.previous().unwrap();
+        let path = self.consume(TokenType::String, "expect module path")?;
+        let path = match path.literal {
+            Some(Object::Str(path)) => path,
+            _ => {
+                return Err(LoxResult::parse_error(
+                    path,
+                    "invalid module path".to_string(),
+                ))
+            }
+        };
+
+        let (alias, names) = if keyword.is(TokenType::Import) {
+            self.consume(TokenType::As, "expect `as` after module path")?;
+            let alias = self.consume(TokenType::Identifier, "expect module name")?;
+            (Some(alias), Vec::new())
+        } else {
+            self.consume(TokenType::Import, "expect `import` after module path")?;
+            let mut names = vec![self.consume(TokenType::Identifier, "expect imported name")?];
+            while self.is_match(&vec![TokenType::Comma]) {
+                names.push(self.consume(TokenType::Identifier, "expect imported name")?);
+            }
+            (None, names)
+        };
+        self.consume(TokenType::SemiColon, "expect `;` after import")?;
+
+        Ok(Stmt::Import(ImportStmt {
+            keyword,
+            path,
+            alias,
+            names: Rc::new(names),
+        }))
+    }
+
     // funDecl        → "fun" function ;
     fn func_declaration(&mut self) -> Result<Stmt, LoxResult> {
         self.function("function")
@@ -47,14 +116,20 @@ impl Parser {
     fn function(&mut self, kind: &str) -> Result<Stmt, LoxResult> {
         let name = self.consume(TokenType::Identifier, &format!("expect a {kind} name"))?;
 
+        Ok(Stmt::Function(self.function_body(name, kind)?))
+    }
+
+    // "(" parameters? ")" block ;
+    pub(super) fn function_body(
+        &mut self,
+        name: Token,
+        kind: &str,
+    ) -> Result<FunctionStmt, LoxResult> {
         self.consume(
             TokenType::LeftParen,
             &format!("expect `(` after {kind} name"),
         )?;
-        let mut params: Vec<Token> = vec![];
-        if !self.is_expect(TokenType::RightParen) {
-            params = self.parameters()?;
-        }
+        let parameters = self.parameter_list()?;
         self.consume(TokenType::RightParen, "expect `)` after parameters")?;
 
         // block          → "{" declaration* "}" ; 逻辑与statement 中判断block处理一致，须先将 `{` 匹配处理
@@ -62,38 +137,83 @@ impl Parser {
             TokenType::LeftBrace,
             &format!("expect `{{` before {kind} body"),
         )?;
-        let body = Rc::new(self.block()?);
+        self.function_depth += 1;
+        let body = self.block();
+        self.function_depth -= 1;
+        let body = Rc::new(body?);
 
-        Ok(Stmt::Function(FunctionStmt {
+        Ok(FunctionStmt {
             name,
-            params: Rc::new(params),
+            params: Rc::new(parameters.params),
+            defaults: Rc::new(parameters.defaults),
+            rest: parameters.rest,
             body,
-        }))
+        })
+    }
+
+    pub(super) fn parameter_list(&mut self) -> Result<Parameters, LoxResult> {
+        if self.is_expect(TokenType::RightParen) {
+            Ok(Parameters::default())
+        } else {
+            self.parameters()
+        }
     }
 
     // parameters     → IDENTIFIER ( "," IDENTIFIER )* ;
-    fn parameters(&mut self) -> Result<Vec<Token>, LoxResult> {
-        let mut params: Vec<Token> = vec![];
-        params.push(self.consume(TokenType::Identifier, "expect a param name")?);
+    // parameters     → parameter ( "," parameter )* ( "," "..." IDENTIFIER )? | "..." IDENTIFIER ;
+    // parameter      → IDENTIFIER ( "=" expression )? ;
+    fn parameters(&mut self) -> Result<Parameters, LoxResult> {
+        let mut parameters = Parameters::default();
 
-        while self.is_match(&vec![TokenType::Comma]) {
-            if params.len() >= 255 {
+        loop {
+            if parameters.params.len() >= 255 {
                 return Err(LoxResult::runtime_error(
                     &self.peek().unwrap(),
                     "can't have more than 255 parameters".to_owned(),
                 ));
             }
-            params.push(self.consume(TokenType::Identifier, "expect a param name")?);
+
+            // 剩余参数只能是最后一个
+            if self.is_match(&vec![TokenType::Ellipsis]) {
+                parameters.rest =
+                    Some(self.consume(TokenType::Identifier, "expect a rest param name")?);
+                break;
+            }
+
+            let param = self.consume(TokenType::Identifier, "expect a param name")?;
+            let default = if self.is_match(&vec![TokenType::Equal]) {
+                Some(Rc::new(self.expression()?))
+            } else if parameters.defaults.iter().any(|d| d.is_some()) {
+                return Err(LoxResult::parse_error(
+                    param,
+                    "parameter without default follows default parameter".to_owned(),
+                ));
+            } else {
+                None
+            };
+            parameters.params.push(param);
+            parameters.defaults.push(default);
+
+            if !self.is_match(&vec![TokenType::Comma]) {
+                break;
+            }
         }
 
-        Ok(params)
+        Ok(parameters)
     }
 
     //  varDecl        → "var" IDENTIFIER ( "=" expression )? ";"
+    //                 | "const" IDENTIFIER "=" expression ";" ;
     fn var_declaration(&mut self) -> Result<Stmt, LoxResult> {
+        let constant = self.previous().unwrap().is(TokenType::Const);
         let name = self.consume(TokenType::Identifier, "expect variable name")?;
         let initializer = if self.is_match(&vec![TokenType::Equal]) {
             Some(Rc::new(self.expression()?))
+        } else if constant {
+            return Err(LoxResult::parse_error(
+                name,
+                "expect initializer for constant".to_string(),
+            ));
         } else {
             None
         };
@@ -102,7 +222,11 @@ impl Parser {
             "expect `;` after variable declaration",
         )?;
 
-        Ok(Stmt::Var(VarStmt { name, initializer }))
+        Ok(Stmt::Var(VarStmt {
+            name,
+            initializer,
+            constant,
+        }))
     }
 
     // statement      → exprStmt | printStmt ;
@@ -112,9 +236,14 @@ impl Parser {
     // statement      → exprStmt | forStmt | ifStmt | printStmt | whileStmt | block ;
     // statement      → exprStmt | forStmt | ifStmt | printStmt | whileStmt | block | breakStmt;
     // statement      → exprStmt | forStmt | ifStmt | printStmt | returnStmt | whileStmt | block ;
+    // statement      → ... | throwStmt | tryStmt ;
     fn statement(&mut self) -> Result<Stmt, LoxResult> {
         if self.is_match(&vec![TokenType::Return]) {
             self.return_statement()
+        } else if self.is_match(&vec![TokenType::Throw]) {
+            self.throw_statement()
+        } else if self.is_match(&vec![TokenType::Try]) {
+            self.try_statement()
         } else if self.is_match(&vec![TokenType::Break]) {
             self.break_statement()
         } else if self.is_match(&vec![TokenType::For]) {
@@ -143,8 +272,65 @@ impl Parser {
             Some(Rc::new(self.expression()?))
         };
         self.consume(TokenType::SemiColon, "expect `;` after return")?;
+        let tail_call = self.is_tail_call(&value);
+
+        Ok(Stmt::Return(ReturnStmt {
+            name,
+            value,
+            tail_call,
+        }))
+    }
+
+    // 函数体内 `return f(x);` 形式的返回为尾调用
+    pub(super) fn is_tail_call(&self, value: &Option<Rc<Expr>>) -> bool {
+        self.function_depth > 0 && matches!(value.as_deref(), Some(Expr::Call(_)))
+    }
+
+    // throwStmt      → "throw" expression ";" ;
+    fn throw_statement(&mut self) -> Result<Stmt, LoxResult> {
+        let keyword = self.previous().unwrap();
+        let value = Rc::new(self.expression()?);
+        self.consume(TokenType::SemiColon, "expect `;` after throw value")?;
+
+        Ok(Stmt::Throw(ThrowStmt { keyword, value }))
+    }
+
+    // tryStmt        → "try" block ( "catch" "(" IDENTIFIER ")" block )? ( "finally" block )? ;
+    fn try_statement(&mut self) -> Result<Stmt, LoxResult> {
+        let keyword = self.previous().unwrap();
+        self.consume(TokenType::LeftBrace, "expect `{` after try")?;
+        let body = Rc::new(self.block()?);
+
+        let (catch_name, catch_body) = if self.is_match(&vec![TokenType::Catch]) {
+            self.consume(TokenType::LeftParen, "expect `(` after catch")?;
+            let name = self.consume(TokenType::Identifier, "expect exception name")?;
+            self.consume(TokenType::RightParen, "expect `)` after exception name")?;
+            self.consume(TokenType::LeftBrace, "expect `{` before catch body")?;
+            (Some(name), Some(Rc::new(self.block()?)))
+        } else {
+            (None, None)
+        };
+
+        let finally_body = if self.is_match(&vec![TokenType::Finally]) {
+            self.consume(TokenType::LeftBrace, "expect `{` after finally")?;
+            Some(Rc::new(self.block()?))
+        } else {
+            None
+        };
+
+        if catch_body.is_none() && finally_body.is_none() {
+            return Err(LoxResult::parse_error(
+                keyword,
+                "expect `catch` or `finally` after try block".to_string(),
+            ));
+        }
 
-        Ok(Stmt::Return(ReturnStmt { name, value }))
+        Ok(Stmt::Try(TryStmt {
+            body,
+            catch_name,
+            catch_body,
+            finally_body,
+        }))
     }
 
     // breakStmt      → "break" ";" ;
@@ -166,43 +352,26 @@ impl Parser {
         };
 
         let condition = if self.is_expect(TokenType::SemiColon) {
-            Some(Expr::Literal(LiteralExpr {
-                value: Object::True,
-            }))
+            None
         } else {
-            Some(self.expression()?)
+            Some(Rc::new(self.expression()?))
         };
         self.consume(TokenType::SemiColon, "expect `;` in `for` middle")?;
 
-        let increment = if self.is_expect(TokenType::SemiColon) {
+        let increment = if self.is_expect(TokenType::RightParen) {
             None
         } else {
-            Some(self.expression()?)
+            Some(Rc::new(self.expression()?))
         };
         self.consume(TokenType::RightParen, "expect `)` after `for` end")?;
 
-        let mut body = self.statement()?;
-        if let Some(expression) = increment {
-            body = Stmt::Block(BlockStmt {
-                statements: Rc::new(vec![
-                    Rc::new(body),
-                    Rc::new(Stmt::Expression(ExpressionStmt {
-                        expression: Rc::new(expression),
-                    })),
-                ]),
-            })
-        }
-        body = Stmt::While(WhileStmt {
-            condition: Rc::new(condition.unwrap()),
-            body: Rc::new(body),
-        });
-        if let Some(init) = initializer {
-            body = Stmt::Block(BlockStmt {
-                statements: Rc::new(vec![Rc::new(init), Rc::new(body)]),
-            });
-        }
-
-        Ok(body)
+        // 不再展开为 while，循环变量在每次迭代中都是新的绑定
+        Ok(Stmt::For(ForStmt {
+            initializer: initializer.map(Rc::new),
+            condition,
+            increment,
+            body: Rc::new(self.statement()?),
+        }))
     }
 
     // whileStmt      → "while" "(" expression ")" statement ;
@@ -237,7 +406,7 @@ impl Parser {
     }
 
     // block          → "{" declaration* "}" ;
-    fn block(&mut self) -> Result<Vec<Rc<Stmt>>, LoxResult> {
+    pub(super) fn block(&mut self) -> Result<Vec<Rc<Stmt>>, LoxResult> {
         let mut statements: Vec<Rc<Stmt>> = vec![];
         while !self.is_expect(TokenType::RightBrace) && !self.is_at_end() {
             statements.push(Rc::new(self.declaration()?));
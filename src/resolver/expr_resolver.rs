@@ -4,35 +4,66 @@ use crate::expr::*;
 use super::Resolver;
 
 impl ExprVisitor<()> for Resolver {
-    fn visit_assign_expr(&self, _expr: &AssignExpr) -> Result<(), LoxResult> {
-        Ok(())
+    fn visit_assign_expr(&self, expr: &AssignExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(&expr.value)?;
+        self.check_assign(&expr.name)
     }
 
-    fn visit_binary_expr(&self, _expr: &BinaryExpr) -> Result<(), LoxResult> {
-        Ok(())
+    fn visit_binary_expr(&self, expr: &BinaryExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(&expr.left)?;
+        self.resolve_expr(&expr.right)
     }
 
-    fn visit_call_expr(&self, _expr: &CallExpr) -> Result<(), LoxResult> {
+    fn visit_call_expr(&self, expr: &CallExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(&expr.callee)?;
+        for argument in expr.arguments.iter() {
+            self.resolve_expr(argument)?;
+        }
+
         Ok(())
     }
 
-    fn visit_grouping_expr(&self, _expr: &GroupingExpr) -> Result<(), LoxResult> {
-        Ok(())
+    fn visit_grouping_expr(&self, expr: &GroupingExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(&expr.expression)
     }
 
     fn visit_literal_expr(&self, _expr: &LiteralExpr) -> Result<(), LoxResult> {
         Ok(())
     }
 
-    fn visit_logical_expr(&self, _expr: &LogicalExpr) -> Result<(), LoxResult> {
-        Ok(())
+    fn visit_logical_expr(&self, expr: &LogicalExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(&expr.left)?;
+        self.resolve_expr(&expr.right)
     }
 
-    fn visit_unary_expr(&self, _expr: &UnaryExpr) -> Result<(), LoxResult> {
-        Ok(())
+    fn visit_unary_expr(&self, expr: &UnaryExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(&expr.right)
     }
 
     fn visit_variable_expr(&self, _expr: &VariableExpr) -> Result<(), LoxResult> {
         Ok(())
     }
+
+    fn visit_compound_assign_expr(&self, expr: &CompoundAssignExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(&expr.value)?;
+        self.check_assign(&expr.name)
+    }
+
+    fn visit_update_expr(&self, expr: &UpdateExpr) -> Result<(), LoxResult> {
+        self.check_assign(&expr.name)
+    }
+
+    fn visit_conditional_expr(&self, expr: &ConditionalExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(&expr.condition)?;
+        self.resolve_expr(&expr.then_branch)?;
+        self.resolve_expr(&expr.else_branch)
+    }
+
+    fn visit_lambda_expr(&self, expr: &LambdaExpr) -> Result<(), LoxResult> {
+        self.resolve_function(&expr.declaration)
+    }
+
+    fn visit_get_expr(&self, expr: &GetExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(&expr.object)
+    }
 }
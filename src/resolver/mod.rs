@@ -5,18 +5,32 @@ use crate::{error::LoxResult, expr::Expr, interpreter::Interpreter, stmt::Stmt,
 mod expr_resolver;
 mod stmt_resolver;
 
+// 作用域中的名称：是否已定义，是否为常量
+#[derive(Debug, Clone, Copy)]
+struct Binding {
+    defined: bool,
+    constant: bool,
+}
+
 pub struct Resolver {
-    interpreter: Interpreter,
-    scopes: RefCell<Vec<RefCell<HashMap<String, bool>>>>,
+    // 最外层为脚本的全局作用域
+    scopes: RefCell<Vec<RefCell<HashMap<String, Binding>>>>,
 }
 
-impl Resolver {
-    pub fn new(interpreter: Interpreter) -> Self {
+impl Default for Resolver {
+    fn default() -> Self {
         Self {
-            interpreter,
-            scopes: RefCell::new(Vec::new()),
+            scopes: RefCell::new(vec![RefCell::new(HashMap::new())]),
         }
     }
+}
+
+impl Resolver {
+    // 解析不再需要解释器，保留原签名以兼容已有的调用方
+    #[deprecated(note = "the resolver no longer uses the interpreter, use `Resolver::default()`")]
+    pub fn new(_interpreter: Interpreter) -> Self {
+        Self::default()
+    }
 
     pub fn resolve(&self, statements: &[Rc<Stmt>]) -> Result<(), LoxResult> {
         for stmt in statements {
@@ -42,25 +56,131 @@ impl Resolver {
         self.scopes.borrow_mut().pop();
     }
 
-    fn declare(&self, token: &Token) {
-        if !self.scopes.borrow().is_empty() {
-            self.scopes
-                .borrow()
-                .last()
-                .unwrap()
-                .borrow_mut()
-                .insert(token.as_string(), false);
+    // 同一作用域中不能重新声明常量
+    fn declare(&self, token: &Token, constant: bool) -> Result<(), LoxResult> {
+        let scopes = self.scopes.borrow();
+        let mut scope = scopes.last().unwrap().borrow_mut();
+        if scope.get(&token.lexeme).is_some_and(|b| b.constant) {
+            return Err(LoxResult::parse_error(
+                token.clone(),
+                format!("cannot redeclare constant `{}`", token.lexeme),
+            ));
         }
+        scope.insert(
+            token.as_string(),
+            Binding {
+                defined: false,
+                constant,
+            },
+        );
+
+        Ok(())
     }
 
     fn define(&self, token: &Token) {
-        if !self.scopes.borrow().is_empty() {
-            self.scopes
-                .borrow()
-                .last()
-                .unwrap()
-                .borrow_mut()
-                .insert(token.as_string(), true);
+        if let Some(binding) = self
+            .scopes
+            .borrow()
+            .last()
+            .unwrap()
+            .borrow_mut()
+            .get_mut(&token.lexeme)
+        {
+            binding.defined = true;
+        }
+    }
+
+    // 由内向外查找名称，常量不能被赋值；找不到的名称留给运行时检查
+    fn check_assign(&self, token: &Token) -> Result<(), LoxResult> {
+        let constant = self
+            .scopes
+            .borrow()
+            .iter()
+            .rev()
+            .find_map(|scope| scope.borrow().get(&token.lexeme).copied())
+            .is_some_and(|binding| binding.constant);
+        if constant {
+            return Err(LoxResult::parse_error(
+                token.clone(),
+                format!("cannot assign to constant `{}`", token.lexeme),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use crate::error::LoxResult;
+    use crate::interpreter::Interpreter;
+    use crate::parser::Parser;
+    use crate::scanner::Scanner;
+
+    use super::Resolver;
+
+    fn resolve(source: &str) -> Result<(), LoxResult> {
+        let mut scanner = Scanner::new(source.to_string());
+        let tokens = scanner.scan_tokens()?;
+        let statements: Vec<_> = Parser::new(tokens)
+            .parse()?
+            .into_iter()
+            .map(Rc::new)
+            .collect();
+        Resolver::default().resolve(&statements)
+    }
+
+    fn message(source: &str) -> String {
+        match resolve(source) {
+            Err(LoxResult::ParseError { message, .. }) => message,
+            other => panic!("expect parse error, got {:?}", other),
         }
     }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_deprecated_new() {
+        let mut scanner = Scanner::new("const x = 1; x = 2;".to_string());
+        let statements: Vec<_> = Parser::new(scanner.scan_tokens().unwrap())
+            .parse()
+            .unwrap()
+            .into_iter()
+            .map(Rc::new)
+            .collect();
+        let resolver = Resolver::new(Interpreter::new());
+        assert!(resolver.resolve(&statements).is_err());
+    }
+
+    #[test]
+    fn test_assign_to_constant() {
+        let expected = "cannot assign to constant `x`";
+        assert_eq!(message("const x = 1; x = 2;"), expected);
+        assert_eq!(message("const x = 1; x += 2;"), expected);
+        assert_eq!(message("const x = 1; x++;"), expected);
+        assert_eq!(message("const x = 1; func f() { --x; }"), expected);
+        assert_eq!(message("{ const x = 1; var f = () => x = 2; }"), expected);
+        assert_eq!(message("const x;"), "expect initializer for constant");
+    }
+
+    #[test]
+    fn test_constant_shadowing() {
+        // 内层作用域、参数及 catch 变量都可以遮蔽外层常量
+        assert!(resolve("const x = 1; { var x = 2; x = 3; }").is_ok());
+        assert!(resolve("const x = 1; { const x = 2; }").is_ok());
+        assert!(resolve("const x = 1; func f(x) { x = 2; }").is_ok());
+        assert!(resolve("const x = 1; try { throw 1; } catch (x) { x = 2; }").is_ok());
+
+        // 同一作用域中不能重新声明常量，但常量可以遮蔽同一作用域中的变量
+        assert_eq!(
+            message("const x = 1; var x = 2;"),
+            "cannot redeclare constant `x`"
+        );
+        assert_eq!(
+            message("{ const x = 1; func x() {} }"),
+            "cannot redeclare constant `x`"
+        );
+        assert!(resolve("var x = 1; const x = 2;").is_ok());
+    }
 }
@@ -1,5 +1,8 @@
+use std::rc::Rc;
+
 use crate::error::LoxResult;
 use crate::stmt::*;
+use crate::token::Token;
 
 use super::Resolver;
 
@@ -15,28 +18,40 @@ impl StmtVisitor<()> for Resolver {
         Ok(())
     }
 
-    fn visit_expression_stmt(&self, _stmt: &ExpressionStmt) -> Result<(), LoxResult> {
-        Ok(())
+    fn visit_expression_stmt(&self, stmt: &ExpressionStmt) -> Result<(), LoxResult> {
+        self.resolve_expr(&stmt.expression)
     }
 
-    fn visit_function_stmt(&self, _stmt: &FunctionStmt) -> Result<(), LoxResult> {
-        Ok(())
+    fn visit_function_stmt(&self, stmt: &FunctionStmt) -> Result<(), LoxResult> {
+        self.declare(&stmt.name, false)?;
+        self.define(&stmt.name);
+        self.resolve_function(stmt)
     }
 
-    fn visit_if_stmt(&self, _stmt: &IfStmt) -> Result<(), LoxResult> {
+    fn visit_if_stmt(&self, stmt: &IfStmt) -> Result<(), LoxResult> {
+        self.resolve_expr(&stmt.condition)?;
+        self.resolve_stmt(&stmt.then_branch)?;
+        if let Some(ref else_branch) = stmt.else_branch {
+            self.resolve_stmt(else_branch)?;
+        }
+
         Ok(())
     }
 
-    fn visit_print_stmt(&self, _stmt: &PrintStmt) -> Result<(), LoxResult> {
-        Ok(())
+    fn visit_print_stmt(&self, stmt: &PrintStmt) -> Result<(), LoxResult> {
+        self.resolve_expr(&stmt.expression)
     }
 
-    fn visit_return_stmt(&self, _stmt: &ReturnStmt) -> Result<(), LoxResult> {
+    fn visit_return_stmt(&self, stmt: &ReturnStmt) -> Result<(), LoxResult> {
+        if let Some(ref value) = stmt.value {
+            self.resolve_expr(value)?;
+        }
+
         Ok(())
     }
 
     fn visit_var_stmt(&self, stmt: &VarStmt) -> Result<(), LoxResult> {
-        self.declare(&stmt.name);
+        self.declare(&stmt.name, stmt.constant)?;
         if let Some(ref init) = stmt.initializer {
             self.resolve_expr(init)?;
         }
@@ -44,7 +59,92 @@ impl StmtVisitor<()> for Resolver {
         Ok(())
     }
 
-    fn visit_while_stmt(&self, _stmt: &WhileStmt) -> Result<(), LoxResult> {
+    fn visit_while_stmt(&self, stmt: &WhileStmt) -> Result<(), LoxResult> {
+        self.resolve_expr(&stmt.condition)?;
+        self.resolve_stmt(&stmt.body)
+    }
+
+    fn visit_for_stmt(&self, stmt: &ForStmt) -> Result<(), LoxResult> {
+        self.begin_scope();
+        if let Some(ref initializer) = stmt.initializer {
+            self.resolve_stmt(initializer)?;
+        }
+        if let Some(ref condition) = stmt.condition {
+            self.resolve_expr(condition)?;
+        }
+        if let Some(ref increment) = stmt.increment {
+            self.resolve_expr(increment)?;
+        }
+        self.resolve_stmt(&stmt.body)?;
+        self.end_scope();
+
+        Ok(())
+    }
+
+    fn visit_throw_stmt(&self, stmt: &ThrowStmt) -> Result<(), LoxResult> {
+        self.resolve_expr(&stmt.value)
+    }
+
+    fn visit_try_stmt(&self, stmt: &TryStmt) -> Result<(), LoxResult> {
+        self.resolve_scope(&stmt.body, None)?;
+        if let Some(ref catch_body) = stmt.catch_body {
+            self.resolve_scope(catch_body, stmt.catch_name.as_ref())?;
+        }
+        if let Some(ref finally_body) = stmt.finally_body {
+            self.resolve_scope(finally_body, None)?;
+        }
+
+        Ok(())
+    }
+
+    fn visit_import_stmt(&self, stmt: &ImportStmt) -> Result<(), LoxResult> {
+        for name in stmt.names.iter().chain(stmt.alias.as_ref()) {
+            self.declare(name, false)?;
+            self.define(name);
+        }
+
+        Ok(())
+    }
+
+    fn visit_export_stmt(&self, stmt: &ExportStmt) -> Result<(), LoxResult> {
+        self.resolve_stmt(&stmt.declaration)
+    }
+}
+
+impl Resolver {
+    // 参数与函数体位于同一作用域
+    pub(super) fn resolve_function(&self, function: &FunctionStmt) -> Result<(), LoxResult> {
+        self.begin_scope();
+        for (param, default) in function.params.iter().zip(function.defaults.iter()) {
+            if let Some(default) = default {
+                self.resolve_expr(default)?;
+            }
+            self.declare(param, false)?;
+            self.define(param);
+        }
+        if let Some(ref rest) = function.rest {
+            self.declare(rest, false)?;
+            self.define(rest);
+        }
+        self.resolve(&function.body)?;
+        self.end_scope();
+
+        Ok(())
+    }
+
+    fn resolve_scope(
+        &self,
+        statements: &[Rc<Stmt>],
+        name: Option<&Token>,
+    ) -> Result<(), LoxResult> {
+        self.begin_scope();
+        if let Some(name) = name {
+            self.declare(name, false)?;
+            self.define(name);
+        }
+        self.resolve(statements)?;
+        self.end_scope();
+
         Ok(())
     }
 }
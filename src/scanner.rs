@@ -49,11 +49,53 @@ impl Scanner {
                 '{' => self.add_token(TokenType::LeftBrace),
                 '}' => self.add_token(TokenType::RightBrace),
                 ',' => self.add_token(TokenType::Comma),
-                '.' => self.add_token(TokenType::Dot),
-                '-' => self.add_token(TokenType::Minus),
-                '+' => self.add_token(TokenType::Plus),
+                '.' => {
+                    if self.peek() == Some('.') && self.peek_next() == Some('.') {
+                        self.advance();
+                        self.advance();
+                        self.add_token(TokenType::Ellipsis);
+                    } else {
+                        self.add_token(TokenType::Dot);
+                    }
+                }
+                '-' => {
+                    let tk_type = if self.is_match('-') {
+                        TokenType::MinusMinus
+                    } else if self.is_match('=') {
+                        TokenType::MinusEqual
+                    } else {
+                        TokenType::Minus
+                    };
+                    self.add_token(tk_type);
+                }
+                '+' => {
+                    let tk_type = if self.is_match('+') {
+                        TokenType::PlusPlus
+                    } else if self.is_match('=') {
+                        TokenType::PlusEqual
+                    } else {
+                        TokenType::Plus
+                    };
+                    self.add_token(tk_type);
+                }
                 ';' => self.add_token(TokenType::SemiColon),
-                '*' => self.add_token(TokenType::Star),
+                ':' => self.add_token(TokenType::Colon),
+                '?' => {
+                    let tk_type = if self.is_match('?') {
+                        TokenType::QuestionQuestion
+                    } else {
+                        TokenType::Question
+                    };
+                    self.add_token(tk_type);
+                }
+                '*' => {
+                    let tk_type = if self.is_match('=') {
+                        TokenType::StarEqual
+                    } else {
+                        TokenType::Star
+                    };
+                    self.add_token(tk_type);
+                }
                 '!' => {
                     let tk_type = if self.is_match('=') {
                         TokenType::BangEqual
@@ -73,6 +115,8 @@ impl Scanner {
                                 break;
                             }
                         }
+                    } else if self.is_match('=') {
+                        self.add_token(TokenType::SlashEqual);
                     } else {
                         self.add_token(TokenType::Slash);
                     }
@@ -80,6 +124,8 @@ impl Scanner {
                 '=' => {
                     let tk_type = if self.is_match('=') {
                         TokenType::EqualEqual
+                    } else if self.is_match('>') {
+                        TokenType::Arrow
                     } else {
                         TokenType::Equal
                     };
@@ -225,6 +271,15 @@ impl Scanner {
             "while" => Some(TokenType::While),
             "break" => Some(TokenType::Break),
             "continue" => Some(TokenType::Continue),
+            "throw" => Some(TokenType::Throw),
+            "try" => Some(TokenType::Try),
+            "catch" => Some(TokenType::Catch),
+            "finally" => Some(TokenType::Finally),
+            "import" => Some(TokenType::Import),
+            "from" => Some(TokenType::From),
+            "as" => Some(TokenType::As),
+            "export" => Some(TokenType::Export),
+            "const" => Some(TokenType::Const),
             _ => None,
         }
     }
@@ -15,6 +15,11 @@ pub enum Stmt {
     Return(ReturnStmt),
     Break(BreakStmt),
     While(WhileStmt),
+    For(ForStmt),
+    Throw(ThrowStmt),
+    Try(TryStmt),
+    Import(ImportStmt),
+    Export(ExportStmt),
 }
 
 impl Stmt {
@@ -29,6 +34,11 @@ impl Stmt {
             Stmt::Return(b) => b.accept(visitor),
             Stmt::Break(b) => b.accept(visitor),
             Stmt::While(b) => b.accept(visitor),
+            Stmt::For(b) => b.accept(visitor),
+            Stmt::Throw(b) => b.accept(visitor),
+            Stmt::Try(b) => b.accept(visitor),
+            Stmt::Import(b) => b.accept(visitor),
+            Stmt::Export(b) => b.accept(visitor),
         }
     }
 }
@@ -47,6 +57,8 @@ pub struct ExpressionStmt {
 pub struct FunctionStmt {
     pub name: Token,
     pub params: Rc<Vec<Token>>,
+    pub defaults: Rc<Vec<Option<Rc<Expr>>>>,
+    pub rest: Option<Token>,
     pub body: Rc<Vec<Rc<Stmt>>>,
 }
 
@@ -66,12 +78,14 @@ pub struct PrintStmt {
 pub struct VarStmt {
     pub name: Token,
     pub initializer: Option<Rc<Expr>>,
+    pub constant: bool,
 }
 
 #[derive(Debug)]
 pub struct ReturnStmt {
     pub name: Token,
     pub value: Option<Rc<Expr>>,
+    pub tail_call: bool,
 }
 
 #[derive(Debug)]
@@ -85,6 +99,42 @@ pub struct WhileStmt {
     pub body: Rc<Stmt>,
 }
 
+#[derive(Debug)]
+pub struct ForStmt {
+    pub initializer: Option<Rc<Stmt>>,
+    pub condition: Option<Rc<Expr>>,
+    pub increment: Option<Rc<Expr>>,
+    pub body: Rc<Stmt>,
+}
+
+#[derive(Debug)]
+pub struct ThrowStmt {
+    pub keyword: Token,
+    pub value: Rc<Expr>,
+}
+
+#[derive(Debug)]
+pub struct TryStmt {
+    pub body: Rc<Vec<Rc<Stmt>>>,
+    pub catch_name: Option<Token>,
+    pub catch_body: Option<Rc<Vec<Rc<Stmt>>>>,
+    pub finally_body: Option<Rc<Vec<Rc<Stmt>>>>,
+}
+
+#[derive(Debug)]
+pub struct ImportStmt {
+    pub keyword: Token,
+    pub path: String,
+    pub alias: Option<Token>,
+    pub names: Rc<Vec<Token>>,
+}
+
+#[derive(Debug)]
+pub struct ExportStmt {
+    pub keyword: Token,
+    pub declaration: Rc<Stmt>,
+}
+
 impl BlockStmt {
     pub fn accept<T>(&self, visitor: &dyn StmtVisitor<T>) -> Result<T, LoxResult> {
         visitor.visit_block_stmt(self)
@@ -139,6 +189,36 @@ impl WhileStmt {
     }
 }
 
+impl ForStmt {
+    pub fn accept<T>(&self, visitor: &dyn StmtVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_for_stmt(self)
+    }
+}
+
+impl ThrowStmt {
+    pub fn accept<T>(&self, visitor: &dyn StmtVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_throw_stmt(self)
+    }
+}
+
+impl TryStmt {
+    pub fn accept<T>(&self, visitor: &dyn StmtVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_try_stmt(self)
+    }
+}
+
+impl ImportStmt {
+    pub fn accept<T>(&self, visitor: &dyn StmtVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_import_stmt(self)
+    }
+}
+
+impl ExportStmt {
+    pub fn accept<T>(&self, visitor: &dyn StmtVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_export_stmt(self)
+    }
+}
+
 pub trait StmtVisitor<T> {
     fn visit_block_stmt(&self, stmt: &BlockStmt) -> Result<T, LoxResult>;
     fn visit_expression_stmt(&self, stmt: &ExpressionStmt) -> Result<T, LoxResult>;
@@ -149,4 +229,9 @@ pub trait StmtVisitor<T> {
     fn visit_return_stmt(&self, stmt: &ReturnStmt) -> Result<T, LoxResult>;
     fn visit_break_stmt(&self, stmt: &BreakStmt) -> Result<T, LoxResult>;
     fn visit_while_stmt(&self, stmt: &WhileStmt) -> Result<T, LoxResult>;
+    fn visit_for_stmt(&self, stmt: &ForStmt) -> Result<T, LoxResult>;
+    fn visit_throw_stmt(&self, stmt: &ThrowStmt) -> Result<T, LoxResult>;
+    fn visit_try_stmt(&self, stmt: &TryStmt) -> Result<T, LoxResult>;
+    fn visit_import_stmt(&self, stmt: &ImportStmt) -> Result<T, LoxResult>;
+    fn visit_export_stmt(&self, stmt: &ExportStmt) -> Result<T, LoxResult>;
 }
@@ -0,0 +1,37 @@
+// 测试共用的辅助函数：扫描、解析源码并在解释器中执行
+
+use crate::core::Object;
+use crate::error::LoxResult;
+use crate::interpreter::Interpreter;
+use crate::parser::Parser;
+use crate::scanner::Scanner;
+use crate::token::Token;
+use crate::token_type::TokenType;
+
+pub(crate) fn run(interpreter: &Interpreter, source: &str) -> Result<(), LoxResult> {
+    let mut scanner = Scanner::new(source.to_string());
+    let tokens = scanner.scan_tokens()?;
+    for stmt in Parser::new(tokens).parse()? {
+        interpreter.execute(&stmt)?;
+    }
+
+    Ok(())
+}
+
+// 全局变量的值，变量不存在时 panic
+pub(crate) fn get(interpreter: &Interpreter, name: &str) -> Object {
+    let token = Token::new(TokenType::Identifier, name.to_string(), None, 0);
+    let value = interpreter.globals.borrow().get(&token).unwrap();
+    value
+}
+
+// 在新的解释器中对表达式求值
+pub(crate) fn eval(source: &str) -> Result<Object, LoxResult> {
+    let interpreter = Interpreter::new();
+    run(&interpreter, &format!("var result = {};", source))?;
+    Ok(get(&interpreter, "result"))
+}
+
+pub(crate) fn string(s: &str) -> Object {
+    Object::Str(s.to_string())
+}
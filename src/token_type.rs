@@ -18,6 +18,10 @@ pub enum TokenType {
     SemiColon,
     Slash,
     Star,
+    // ?
+    Question,
+    // :
+    Colon,
 
     // one or two character tokens
     // !
@@ -31,6 +35,24 @@ pub enum TokenType {
     GreaterEqual,
     Less,
     LessEqual,
+    // +=
+    PlusEqual,
+    // -=
+    MinusEqual,
+    // *=
+    StarEqual,
+    // /=
+    SlashEqual,
+    // ++
+    PlusPlus,
+    // --
+    MinusMinus,
+    // ??
+    QuestionQuestion,
+    // =>
+    Arrow,
+    // ...
+    Ellipsis,
 
     // literals
     Identifier,
@@ -56,6 +78,15 @@ pub enum TokenType {
     While,
     Break,
     Continue,
+    Throw,
+    Try,
+    Catch,
+    Finally,
+    Import,
+    From,
+    As,
+    Export,
+    Const,
 
     Eof,
 }